@@ -0,0 +1,63 @@
+// Times the byte version of the English rules against the char version on
+// the corpus the equivalence tests in byte_rules.rs use. Run it with
+//
+//     cargo bench --bench rules
+//
+// It doesn't need a benchmark framework: each version gets a warm-up round
+// and the best of several timed rounds is reported.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use phonetic_normalizer::bench::{byte_rules, char_rules, corpus};
+
+const WORDS: usize = 200_000;
+const ROUNDS: usize = 10;
+
+fn best_of(mut round: impl FnMut()) -> Duration {
+    round();
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            round();
+            start.elapsed()
+        })
+        .min()
+        .expect("ROUNDS is not zero")
+}
+
+fn main() {
+    let words = corpus(WORDS);
+    let mut dest = String::new();
+    let mut scratch = String::new();
+
+    let bytes = best_of(|| {
+        for word in &words {
+            assert!(byte_rules(black_box(word), &mut dest));
+            black_box(&dest);
+        }
+    });
+    let chars = best_of(|| {
+        for word in &words {
+            char_rules(black_box(word), &mut dest, &mut scratch);
+            black_box(&dest);
+        }
+    });
+
+    let per_word = |time: Duration| time.as_nanos() as f64 / WORDS as f64;
+    println!("{} words, best of {} rounds", WORDS, ROUNDS);
+    println!(
+        "byte rules: {:>8.1?} {:>6.1} ns/word",
+        bytes,
+        per_word(bytes)
+    );
+    println!(
+        "char rules: {:>8.1?} {:>6.1} ns/word",
+        chars,
+        per_word(chars)
+    );
+    println!(
+        "the byte rules are {:.2}x as fast",
+        chars.as_secs_f64() / bytes.as_secs_f64()
+    );
+}
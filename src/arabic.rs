@@ -0,0 +1,395 @@
+/*
+* Arabic script transliteration and folding of Latin-script Arabic names.
+*
+* Arabic is normally written without short vowels, so "محمد" is really just the
+* consonants m-h-m-d. The many romanizations of that name (Mohammed, Muhammad,
+* Mohamad, Mehmet) only disagree about the vowels and about doubling, so the
+* name folding reduces a Latin name down to the same consonant skeleton. The
+* skeleton is then run through the normal English rules so the result is
+* comparable with other keys from `normalize_word`.
+*/
+
+use crate::ascii_fold::latinize;
+use crate::normalize_word;
+
+/// Transliterate any Arabic script in `text` to Latin letters. Short vowels
+/// are only produced when the text carries vowel marks, so most words come out
+/// as consonants plus long vowels, e.g. "محمد" becomes "mhmd". Persian and Urdu
+/// letters are handled as well. Other characters pass through unchanged.
+pub fn transliterate_arabic(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+
+    let mut article_lam = false;
+    // where the last consonant was written, for shadda to double it
+    let mut consonant_start = 0;
+    let mut consonant_end = 0;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if article_lam {
+            // the lam of the article was already written out as "al-"
+            article_lam = false;
+            continue;
+        }
+        // the letter before this one, looking past any vowel marks on it
+        let prev = chars[..i].iter().rev().find(|&&c| !is_combining_mark(c));
+        let word_start = !prev.is_some_and(|&c| is_arabic_letter(c));
+        let prev_is_alef = prev.is_some_and(|&c| is_alef(c));
+        // s/^ال/al-/;
+        if word_start
+            && is_alef(c)
+            && chars.get(i + 1) == Some(&'\u{0644}')
+            && chars.get(i + 2).is_some_and(|&c| is_arabic_letter(c))
+        {
+            output.push_str("al-");
+            article_lam = true;
+            continue;
+        }
+        let start = output.len();
+        let latin = match c {
+            '\u{0627}' | '\u{0623}' | '\u{0622}' | '\u{0671}' => "a", // ا أ آ ٱ
+            '\u{0625}' => "i",                                        // إ
+            '\u{0628}' => "b",                                        // ب
+            '\u{062A}' => "t",                                        // ت
+            '\u{062B}' => "th",                                       // ث
+            '\u{062C}' => "j",                                        // ج
+            '\u{062D}' => "h",                                        // ح
+            '\u{062E}' => "kh",                                       // خ
+            '\u{062F}' => "d",                                        // د
+            '\u{0630}' => "dh",                                       // ذ
+            '\u{0631}' => "r",                                        // ر
+            '\u{0632}' => "z",                                        // ز
+            '\u{0633}' => "s",                                        // س
+            '\u{0634}' => "sh",                                       // ش
+            '\u{0635}' => "s",                                        // ص
+            '\u{0636}' => "d",                                        // ض
+            '\u{0637}' => "t",                                        // ط
+            '\u{0638}' => "z",                                        // ظ
+            // ع is usually written as the vowel that follows it at the start
+            // of a word (Ali, Omar) and dropped elsewhere (Saad).
+            '\u{0639}' => {
+                if word_start {
+                    "a"
+                } else {
+                    ""
+                }
+            }
+            '\u{063A}' => "gh",             // غ
+            '\u{0641}' => "f",              // ف
+            '\u{0642}' => "q",              // ق
+            '\u{0643}' | '\u{06A9}' => "k", // ك ک
+            '\u{0644}' => "l",              // ل
+            '\u{0645}' => "m",              // م
+            '\u{0646}' => "n",              // ن
+            '\u{0647}' | '\u{06C1}' => "h", // ه ہ
+            '\u{0629}' | '\u{0649}' => "a", // ة ى
+            '\u{0648}' => {
+                // و is a consonant at the start of a word or after an alef
+                // and a long u elsewhere.
+                if word_start || prev_is_alef {
+                    "w"
+                } else {
+                    "u"
+                }
+            }
+            '\u{064A}' | '\u{06CC}' | '\u{06D2}' => {
+                // ي works the same way as و
+                if word_start || prev_is_alef {
+                    "y"
+                } else {
+                    "i"
+                }
+            }
+            '\u{067E}' => "p",                                       // پ
+            '\u{0686}' => "ch",                                      // چ
+            '\u{0698}' => "zh",                                      // ژ
+            '\u{06AF}' => "g",                                       // گ
+            '\u{0679}' => "t",                                       // ٹ
+            '\u{0688}' => "d",                                       // ڈ
+            '\u{0691}' => "r",                                       // ڑ
+            '\u{06BA}' | '\u{06BB}' => "n",                          // ں
+            '\u{064E}' => "a",                                       // fatha
+            '\u{064F}' => "u",                                       // damma
+            '\u{0650}' => "i",                                       // kasra
+            '\u{0621}' | '\u{0624}' | '\u{0626}' => "",              // hamza
+            '\u{064B}'..='\u{064D}' | '\u{0652}' | '\u{0640}' => "", // tanwin, sukun, tatweel
+            '\u{0651}' => {
+                // shadda doubles the previous consonant, which may already be
+                // followed by its vowel mark
+                let consonant = output[consonant_start..consonant_end].to_string();
+                output.insert_str(consonant_end, &consonant);
+                ""
+            }
+            '\u{0660}'..='\u{0669}' | '\u{06F0}'..='\u{06F9}' => {
+                let digit = (c as u32 - if c < '\u{06F0}' { 0x0660 } else { 0x06F0 }) as u8;
+                output.push((b'0' + digit) as char);
+                ""
+            }
+            _ => {
+                output.push(c);
+                ""
+            }
+        };
+        output.push_str(latin);
+        if !is_vowel_mark(c) && c != '\u{0651}' {
+            consonant_start = start;
+            consonant_end = output.len();
+        }
+    }
+    output
+}
+
+/// Normalize a full Arabic name, in Arabic script or any common romanization,
+/// to a space separated list of keys. The definite article is dropped from
+/// before a name, whether it is hyphenated or a separate word, and "abd al-"
+/// compounds are joined, so "Abdul Rahman", "Abd al-Rahman" and
+/// "Abdurrahman" all give the same result. Each key is a consonant skeleton
+/// that has been through `normalize_word`.
+///
+/// ```
+/// use phonetic_normalizer::normalize_arabic_name;
+///
+/// assert_eq!(normalize_arabic_name("محمد"), normalize_arabic_name("Mohammed"));
+/// assert_eq!(normalize_arabic_name("al-Hassan"), normalize_arabic_name("El Hasan"));
+/// ```
+pub fn normalize_arabic_name(name: &str) -> String {
+    let mut latin = latinize(&transliterate_arabic(name)).into_owned();
+    latin.make_ascii_lowercase();
+
+    let mut keys: Vec<String> = Vec::new();
+    let mut pending_abd = false;
+    let mut tokens = latin
+        .split_whitespace()
+        .map(|token| token.trim_matches(|c: char| !c.is_ascii_alphanumeric()))
+        .filter(|token| !token.is_empty())
+        .peekable();
+    while let Some(token) = tokens.next() {
+        // A separate article only goes if there is a name after it for it to
+        // belong to, so "Ed" or "An" on their own are kept.
+        if is_article(token) && tokens.peek().is_some() {
+            continue;
+        }
+        let folded = if pending_abd {
+            let last = keys.pop().unwrap_or_default();
+            fold_arabic_name(&format!("{}{}", last, strip_article(token)))
+        } else {
+            fold_arabic_name(token)
+        };
+        // "Abd", "Abdul" and friends are only the first half of a name
+        pending_abd = is_abd(token);
+        keys.push(folded);
+    }
+
+    keys.iter()
+        .map(|skeleton| normalize_word(skeleton).into_owned())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Fold a single latinized, lowercase word of an Arabic name to its consonant
+/// skeleton. The skeleton still needs the English rules to become a key.
+pub(crate) fn fold_arabic_name(word: &str) -> String {
+    let word: String = word
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .replace("ph", "f");
+    let mut word = strip_article(&word).to_string();
+
+    // s/^abd[aeiou]?(l|(?=[sun letter]))-?/abd/;
+    if word.starts_with("abd") && word.len() > 3 {
+        let rest = word[3..].trim_start_matches('-');
+        let rest = match rest.as_bytes() {
+            [v, b'l', ..] if is_vowel(*v) => &rest[2..],
+            [v, c, d, ..] if is_vowel(*v) && c == d && is_sun_letter(*c) => &rest[2..],
+            [v, c, ..] if is_vowel(*v) && is_sun_letter(*c) => &rest[1..],
+            _ => rest,
+        };
+        word = format!("abd{}", rest.trim_start_matches('-'));
+    }
+
+    let bytes: Vec<u8> = word.bytes().filter(|b| *b != b'-').collect();
+    let mut skeleton = String::with_capacity(bytes.len());
+    for (i, &b) in bytes.iter().enumerate() {
+        let prev = if i > 0 { Some(bytes[i - 1]) } else { None };
+        let next = bytes.get(i + 1).copied();
+        let c = match b {
+            // Omar/Umar, Ibrahim/Ebrahim: only keep that the name starts
+            // with a vowel, not which one.
+            _ if i == 0 && is_vowel(b) => b'a',
+            _ if is_vowel(b) => continue,
+            // y and w after a vowel are part of a diphthong (Husayn/Hussein)
+            b'y' | b'w' if prev.is_some_and(is_vowel) && !next.is_some_and(is_vowel) => continue,
+            // a trailing h after a vowel is usually ta marbuta (Fatimah)
+            b'h' if next.is_none() && prev.is_some_and(is_vowel) => continue,
+            b'q' => b'k',
+            _ => b,
+        };
+        if skeleton.as_bytes().last() != Some(&c) {
+            skeleton.push(c as char);
+        }
+    }
+    skeleton
+}
+
+// Removes a leading definite article with a hyphen: al-, el-, ul- and the
+// sun letter assimilations like ar- (ar-Rashid) and ash- (ash-Shami).
+fn strip_article(word: &str) -> &str {
+    if let Some(hyphen) = word.find('-') {
+        let (article, rest) = (&word[..hyphen], &word[hyphen + 1..]);
+        if !rest.is_empty() && is_article(article) {
+            return rest;
+        }
+    }
+    word
+}
+
+fn is_article(word: &str) -> bool {
+    match word.as_bytes() {
+        [v, b'l'] => is_vowel(*v),
+        [b'a', rest @ ..] | [b'e', rest @ ..] => {
+            matches!(
+                rest,
+                b"t" | b"th" | b"d" | b"dh" | b"r" | b"z" | b"s" | b"sh" | b"n"
+            )
+        }
+        _ => false,
+    }
+}
+
+fn is_abd(word: &str) -> bool {
+    matches!(
+        word,
+        "abd" | "abdul" | "abdel" | "abdal" | "abdol" | "abdu" | "abdur" | "abdus"
+    )
+}
+
+fn is_vowel(b: u8) -> bool {
+    matches!(b, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+// The letters that the article "al" assimilates to, as they are romanized.
+fn is_sun_letter(b: u8) -> bool {
+    matches!(b, b't' | b'd' | b'r' | b'z' | b's' | b'l' | b'n')
+}
+
+fn is_vowel_mark(c: char) -> bool {
+    matches!(c, '\u{064B}'..='\u{0650}' | '\u{0652}')
+}
+
+// The vowel marks, shadda, sukun and the other marks written over or under
+// a letter.
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}')
+}
+
+fn is_alef(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0627}' | '\u{0623}' | '\u{0625}' | '\u{0622}' | '\u{0671}'
+    )
+}
+
+fn is_arabic_letter(c: char) -> bool {
+    matches!(c, '\u{0620}'..='\u{064A}' | '\u{0671}'..='\u{06D3}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterates_arabic_script() {
+        assert_eq!(transliterate_arabic("محمد"), "mhmd");
+        assert_eq!(transliterate_arabic("حسن"), "hsn");
+        assert_eq!(transliterate_arabic("عبد الرحمن"), "abd al-rhmn");
+        assert_eq!(transliterate_arabic("عمر"), "amr");
+        assert_eq!(transliterate_arabic("مُحَمَّد"), "muhammad");
+        assert_eq!(transliterate_arabic("فاطمة"), "fatma");
+        assert_eq!(transliterate_arabic("١٢٣"), "123");
+        assert_eq!(transliterate_arabic("Ali"), "Ali");
+        // a letter after a vowel mark is in the middle of the word
+        assert_eq!(transliterate_arabic("سَعِيد"), "saiid");
+        assert_eq!(transliterate_arabic("نُور"), "nuur");
+        // shadda doubles both letters of a digraph
+        assert_eq!(transliterate_arabic("رَشَّد"), "rashshad");
+    }
+
+    #[test]
+    fn arabic_name_variants() {
+        let muhammad = normalize_arabic_name("محمد");
+        for variant in &["Mohammed", "Muhammad", "Mohamad", "Mehmet", "Mohammad"] {
+            assert_eq!(normalize_arabic_name(variant), muhammad, "{}", variant);
+        }
+        assert_eq!(
+            normalize_arabic_name("Abdul Rahman"),
+            normalize_arabic_name("Abdurrahman")
+        );
+        assert_eq!(
+            normalize_arabic_name("Abd al-Rahman"),
+            normalize_arabic_name("Abdurrahman")
+        );
+        assert_eq!(
+            normalize_arabic_name("عبد الرحمن"),
+            normalize_arabic_name("Abdul Rahman")
+        );
+        assert_eq!(
+            normalize_arabic_name("al-Hassan"),
+            normalize_arabic_name("El Hasan")
+        );
+        assert_eq!(
+            normalize_arabic_name("Hussein"),
+            normalize_arabic_name("Husayn")
+        );
+        assert_eq!(normalize_arabic_name("Omar"), normalize_arabic_name("Umar"));
+        assert_eq!(
+            normalize_arabic_name("Fatimah"),
+            normalize_arabic_name("Fatima")
+        );
+        assert_eq!(
+            normalize_arabic_name("Youssef"),
+            normalize_arabic_name("Yusuf")
+        );
+        assert_eq!(
+            normalize_arabic_name("Qasim"),
+            normalize_arabic_name("Kasim")
+        );
+        assert_eq!(
+            normalize_arabic_name("Ahmed al-Hassan"),
+            normalize_arabic_name("Ahmad El Hasan")
+        );
+    }
+
+    #[test]
+    fn arabic_name_mismatches() {
+        assert_ne!(
+            normalize_arabic_name("Hassan"),
+            normalize_arabic_name("Hussam")
+        );
+        assert_ne!(normalize_arabic_name("Ali"), normalize_arabic_name("Omar"));
+        assert_ne!(
+            normalize_arabic_name("Karim"),
+            normalize_arabic_name("Kamal")
+        );
+    }
+
+    #[test]
+    fn lone_articles_are_kept() {
+        for word in &["an", "as", "at", "ed", "en", "el", "Al"] {
+            assert_ne!(normalize_arabic_name(word), "", "{}", word);
+        }
+        assert_ne!(normalize_arabic_name("Ed"), normalize_arabic_name("En"));
+        assert_eq!(
+            normalize_arabic_name("Hasan al"),
+            normalize_arabic_name("Hasan El")
+        );
+        assert_ne!(
+            normalize_arabic_name("Hasan al"),
+            normalize_arabic_name("Hasan")
+        );
+        assert_eq!(
+            normalize_arabic_name("el Hasan"),
+            normalize_arabic_name("Hasan")
+        );
+    }
+}
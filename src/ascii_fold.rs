@@ -10,17 +10,184 @@
 * Each function is individually labeled as well.
 */
 
+use std::borrow::Cow;
+
+use unicode_normalization::char::{decompose_compatible, is_combining_mark};
+use unicode_normalization::UnicodeNormalization;
+
 // This function is inspired by the to_ascii function in
-// tantivy-search's ascii_folding_filter
-pub fn latinize(text: &str) -> String {
+// tantivy-search's ascii_folding_filter. Text that doesn't need folding, which
+// includes all ASCII text, is returned as-is without copying.
+pub fn latinize(text: &str) -> Cow<'_, str> {
+    let first = match text.char_indices().find(|(_, c)| needs_folding(*c)) {
+        Some((i, _)) => i,
+        None => return Cow::Borrowed(text),
+    };
     let mut output = String::with_capacity(text.len());
+    output.push_str(&text[..first]);
+    latinize_into(&text[first..], &mut output);
+    Cow::Owned(output)
+}
+
+// This function is ours. Appends the folded `text` to `output`.
+pub(crate) fn latinize_into(text: &str, output: &mut String) {
+    if text.is_ascii() {
+        output.push_str(text);
+        return;
+    }
     for c in text.chars() {
-        match fold_non_ascii_char(c) {
+        push_folded(c, output);
+    }
+}
+
+// This function is ours. Whether `push_folded` would write anything other
+// than `c` itself.
+fn needs_folding(c: char) -> bool {
+    if c.is_ascii() {
+        return false;
+    }
+    if fold_non_ascii_char(c).is_some() || is_combining_mark(c) {
+        return true;
+    }
+    let mut changed = false;
+    decompose_compatible(c, |d| changed |= d != c);
+    changed
+}
+
+// This function is ours. Characters the table knows are folded with it, since
+// it does better than decomposition for things like ½ and ß. Anything else
+// that decomposes to a Latin letter is given its compatibility decomposition
+// (NFKD) with the combining marks stripped, and the pieces are looked up in
+// turn. That covers decomposed input, where "e" is followed by a combining
+// acute, the mathematical alphanumerics and the Latin letters added to
+// Unicode since the table was made. The marks on other scripts, like the
+// vowel signs of Devanagari and the dakuten of kana, tell letters apart, so
+// those are left as they are.
+fn push_folded(c: char, output: &mut String) {
+    if c.is_ascii() {
+        output.push(c);
+        return;
+    }
+    if let Some(folded) = fold_non_ascii_char(c) {
+        output.push_str(folded);
+        return;
+    }
+    if is_combining_mark(c) {
+        // a mark on its own belongs to the letter before it
+        let base = output.chars().last().unwrap_or(' ');
+        if !is_latin(base) {
+            output.push(c);
+        }
+        return;
+    }
+    let mut base = None;
+    let mut marked = false;
+    decompose_compatible(c, |d| {
+        base = base.or(Some(d));
+        marked |= is_combining_mark(d);
+    });
+    if marked && !base.is_some_and(is_latin) {
+        output.push(c);
+        return;
+    }
+    decompose_compatible(c, |d| {
+        if is_combining_mark(d) {
+            return;
+        }
+        match fold_non_ascii_char(d) {
             Some(folded) => output.push_str(folded),
-            None => output.push(c),
+            None => output.push(d),
         }
+    });
+}
+
+// This function is ours. Whether `c` is a Latin letter, or anything else that
+// isn't a letter of another script, so the marks on it can be dropped.
+fn is_latin(c: char) -> bool {
+    c.is_ascii()
+        || !c.is_alphabetic()
+        || fold_non_ascii_char(c).is_some()
+        || matches!(
+            c,
+            '\u{00C0}'..='\u{024F}'
+                | '\u{1E00}'..='\u{1EFF}'
+                | '\u{2C60}'..='\u{2C7F}'
+                | '\u{A720}'..='\u{A7FF}'
+                | '\u{AB30}'..='\u{AB6F}'
+        )
+}
+
+/// A language whose conventions for writing its letters in ASCII differ from
+/// the general fold table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Locale {
+    /// Dotted and dotless i are told apart when lowercasing, so "I" is "ı"
+    /// and "İ" is "i".
+    Turkish,
+    /// Umlauts become e digraphs, so "Müller" is "Mueller".
+    German,
+    /// Å becomes aa and ø becomes oe, so "Århus" is "Aarhus".
+    Danish,
+    /// The same as Danish.
+    Norwegian,
+    /// Stacked diacritics are dropped and đ is d, so "Nguyễn" is "Nguyen".
+    Vietnamese,
+}
+
+// This function is ours. It applies the overrides for `locale` and falls back
+// to the general table for everything else.
+pub fn latinize_with(locale: Locale, text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut output = String::with_capacity(text.len());
+    // compose first so that the overrides see decomposed letters too
+    for c in text.nfc() {
+        match fold_locale_char(locale, c) {
+            Some(folded) => output.push_str(folded),
+            None => push_folded(c, &mut output),
+        }
+    }
+    Cow::Owned(output)
+}
+
+// This function is ours. Lowercases `text` following the casing rules of
+// `locale` rather than the default Unicode ones.
+pub(crate) fn lowercase_with(locale: Locale, text: &str) -> String {
+    match locale {
+        Locale::Turkish => text
+            .chars()
+            .map(|c| match c {
+                'I' => '\u{0131}',
+                '\u{0130}' => 'i',
+                _ => c,
+            })
+            .flat_map(char::to_lowercase)
+            .collect(),
+        _ => text.to_lowercase(),
+    }
+}
+
+// This function is ours.
+fn fold_locale_char(locale: Locale, c: char) -> Option<&'static str> {
+    match (locale, c) {
+        (Locale::Turkish, '\u{0131}') => Some("i"), // ı
+        (Locale::Turkish, '\u{0130}') => Some("I"), // İ
+        (Locale::German, '\u{00C4}') => Some("Ae"), // Ä
+        (Locale::German, '\u{00D6}') => Some("Oe"), // Ö
+        (Locale::German, '\u{00DC}') => Some("Ue"), // Ü
+        (Locale::German, '\u{00E4}') => Some("ae"), // ä
+        (Locale::German, '\u{00F6}') => Some("oe"), // ö
+        (Locale::German, '\u{00FC}') => Some("ue"), // ü
+        (Locale::Danish | Locale::Norwegian, '\u{00C5}') => Some("Aa"), // Å
+        (Locale::Danish | Locale::Norwegian, '\u{00E5}') => Some("aa"), // å
+        (Locale::Danish | Locale::Norwegian, '\u{00C6}') => Some("Ae"), // Æ
+        (Locale::Danish | Locale::Norwegian, '\u{00D8}') => Some("Oe"), // Ø
+        (Locale::Danish | Locale::Norwegian, '\u{00F8}') => Some("oe"), // ø
+        (Locale::Vietnamese, '\u{0110}') => Some("D"), // Đ
+        (Locale::Vietnamese, '\u{0111}') => Some("d"), // đ
+        _ => None,
     }
-    output
 }
 
 // This function is fully copied from tantivy-search's ascii_folding_filter
@@ -1527,6 +1694,78 @@ mod tests {
         assert_eq!(&latinize("Æneid"), "AEneid");
     }
 
+    #[test]
+    fn decomposed_and_compatibility_forms() {
+        assert_eq!(&latinize("Jose\u{0301}"), "Jose");
+        assert_eq!(&latinize("Jos\u{00E9}"), "Jose");
+        assert_eq!(&latinize("Nguye\u{0302}\u{0303}n"), "Nguyen");
+        assert_eq!(&latinize("\u{1D400}\u{1D41B}\u{1D41A}"), "Aba"); // 𝐀𝐛𝐚
+        assert_eq!(&latinize("\u{FF2A}\u{FF4F}\u{FF53}\u{FF45}"), "Jose"); // fullwidth
+        assert_eq!(&latinize("\u{2460}"), "1"); // ①
+        assert_eq!(&latinize("\u{A7B6}"), "\u{A7B6}"); // no decomposition
+        assert_eq!(&latinize("\u{00BD}"), "1/2"); // the table still wins
+        assert_eq!(&latinize_with(Locale::German, "Mu\u{0308}ller"), "Mueller");
+    }
+
+    #[test]
+    fn other_scripts_keep_their_marks() {
+        // Devanagari vowel signs and the anusvara
+        assert_eq!(&latinize("हिंदी"), "हिंदी");
+        assert_eq!(&latinize("हिंदी Noël"), "हिंदी Noel");
+        // kana with dakuten, precomposed and decomposed
+        assert_eq!(&latinize("が"), "が");
+        assert_eq!(&latinize("か\u{3099}"), "か\u{3099}");
+        assert_eq!(&latinize("й"), "й");
+        assert_ne!(crate::normalize_word("が"), crate::normalize_word("か"));
+        assert_ne!(crate::normalize_word("हिंदी"), crate::normalize_word("हद"));
+    }
+
+    #[test]
+    fn locale_overrides() {
+        assert_eq!(&latinize_with(Locale::German, "Müller"), "Mueller");
+        assert_eq!(&latinize_with(Locale::German, "Öztürk"), "Oeztuerk");
+        assert_eq!(&latinize_with(Locale::German, "Straße"), "Strasse");
+        assert_eq!(&latinize_with(Locale::Danish, "Århus"), "Aarhus");
+        assert_eq!(&latinize_with(Locale::Danish, "Søren"), "Soeren");
+        assert_eq!(&latinize_with(Locale::Norwegian, "Bjørn"), "Bjoern");
+        assert_eq!(&latinize_with(Locale::Turkish, "Yıldız"), "Yildiz");
+        assert_eq!(&latinize_with(Locale::Turkish, "Öztürk"), "Ozturk");
+        assert_eq!(&latinize_with(Locale::Vietnamese, "Nguyễn"), "Nguyen");
+        assert_eq!(
+            &latinize_with(Locale::Vietnamese, "Nguye\u{0302}\u{0303}n"),
+            "Nguyen"
+        );
+        assert_eq!(&latinize_with(Locale::Vietnamese, "Đặng"), "Dang");
+        // everything else still goes through the general table
+        assert_eq!(&latinize_with(Locale::German, "François"), "Francois");
+    }
+
+    #[test]
+    fn locale_lowercase() {
+        assert_eq!(lowercase_with(Locale::Turkish, "IŞIK"), "ışık");
+        assert_eq!(lowercase_with(Locale::Turkish, "İSTANBUL"), "istanbul");
+        assert_eq!(
+            lowercase_with(Locale::German, "İSTANBUL"),
+            "i\u{0307}stanbul"
+        );
+        assert_eq!(lowercase_with(Locale::German, "MÜLLER"), "müller");
+    }
+
+    #[test]
+    fn unchanged_text_is_borrowed() {
+        assert!(matches!(latinize("Smith"), Cow::Borrowed("Smith")));
+        assert!(matches!(latinize(""), Cow::Borrowed("")));
+        // nothing to fold in text that isn't Latin
+        assert!(matches!(latinize("Иван"), Cow::Borrowed(_)));
+        assert!(matches!(latinize("Noël"), Cow::Owned(_)));
+        assert!(matches!(latinize("Noe\u{0308}l"), Cow::Owned(_)));
+        assert!(matches!(
+            latinize_with(Locale::German, "Mueller"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(latinize("Иван Noël"), "Иван Noel");
+    }
+
     // I did not copy the test_all_foldings test from tantivy since it's thousands
     // of lines long and it is mostly a duplicate of the fold_non_ascii_char
     // function with the u-codes turned into actual characters.
@@ -0,0 +1,154 @@
+/*
+* Normalizing many words at once.
+*
+* With the `rayon` feature (on by default) the words are split across
+* rayon's thread pool. Each piece of work gets its own `Normalizer`, so the
+* scratch buffers are reused within a thread and never shared between them,
+* and the keys are collected back in the order of the input. Without the
+* feature the same functions run on the calling thread.
+*/
+
+use crate::normalizer::{Normalizer, Options};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// How many words `normalize_iter` reads ahead and normalizes together. Big
+// enough for the threads to have plenty to do, small enough that streaming a
+// large file doesn't hold much of it in memory.
+const BATCH_SIZE: usize = 8192;
+
+/// Normalize every word in `words` with the default options, returning the
+/// keys in the same order.
+///
+/// ```
+/// use phonetic_normalizer::normalize_batch;
+///
+/// let keys = normalize_batch(&["Philip", "Phillip", "Kathryn"]);
+/// assert_eq!(keys, ["filip", "filip", "kathren"]);
+/// ```
+pub fn normalize_batch(words: &[&str]) -> Vec<String> {
+    normalize_batch_with(words, &Options::default())
+}
+
+/// Like `normalize_batch`, but with the stages enabled in `options`.
+pub fn normalize_batch_with<S>(words: &[S], options: &Options) -> Vec<String>
+where
+    S: AsRef<str> + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        words
+            .par_iter()
+            .map_init(
+                || Normalizer::new(options.clone()),
+                |normalizer, word| key(normalizer, word.as_ref()),
+            )
+            .collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        let mut normalizer = Normalizer::new(options.clone());
+        words
+            .iter()
+            .map(|word| key(&mut normalizer, word.as_ref()))
+            .collect()
+    }
+}
+
+/// Normalize the words from an iterator, yielding each one with its key in
+/// the order they came in. Words are read and normalized in batches, so this
+/// works on streams too large to hold in memory, like the lines of a file.
+///
+/// ```
+/// use phonetic_normalizer::{normalize_iter, Options};
+///
+/// let words = "color colour cooler".split_whitespace();
+/// let keys: Vec<(&str, String)> = normalize_iter(words, &Options::default()).collect();
+/// assert_eq!(keys[0], ("color", "kolor".to_string()));
+/// assert_eq!(keys[2], ("cooler", "koolr".to_string()));
+/// ```
+pub fn normalize_iter<I>(words: I, options: &Options) -> NormalizeIter<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str> + Sync,
+{
+    NormalizeIter {
+        words: words.into_iter(),
+        options: options.clone(),
+        batch: Vec::new().into_iter(),
+    }
+}
+
+/// The iterator returned by `normalize_iter`.
+pub struct NormalizeIter<I: Iterator> {
+    words: I,
+    options: Options,
+    // the normalized words that haven't been yielded yet
+    batch: std::vec::IntoIter<(I::Item, String)>,
+}
+
+impl<I> Iterator for NormalizeIter<I>
+where
+    I: Iterator,
+    I::Item: AsRef<str> + Sync,
+{
+    type Item = (I::Item, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(next) = self.batch.next() {
+            return Some(next);
+        }
+        let words: Vec<I::Item> = self.words.by_ref().take(BATCH_SIZE).collect();
+        let keys = normalize_batch_with(&words, &self.options);
+        self.batch = words.into_iter().zip(keys).collect::<Vec<_>>().into_iter();
+        self.batch.next()
+    }
+}
+
+fn key(normalizer: &mut Normalizer, word: &str) -> String {
+    let mut key = String::new();
+    normalizer.normalize_into(word, &mut key);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{normalize_word, normalize_word_with, Script};
+
+    #[test]
+    fn batch_keeps_order() {
+        let words: Vec<String> = (0..20_000).map(|i| format!("word{}ough", i)).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let keys = normalize_batch(&words);
+        assert_eq!(keys.len(), words.len());
+        for (word, key) in words.iter().zip(&keys) {
+            assert_eq!(key, &normalize_word(word));
+        }
+    }
+
+    #[test]
+    fn batch_with_options() {
+        let options = Options::new().script(Script::Greek);
+        let words = ["Παπαδόπουλος", "Papadopoulos", "Γιώργος"];
+        let keys = normalize_batch_with(&words, &options);
+        for (word, key) in words.iter().zip(&keys) {
+            assert_eq!(key, &normalize_word_with(word, &options));
+        }
+        assert!(normalize_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn iter_spans_batches() {
+        let count = BATCH_SIZE * 2 + 17;
+        let words = (0..count).map(|i| format!("Phillip{}", i));
+        let mut seen = 0;
+        for (i, (word, key)) in normalize_iter(words, &Options::default()).enumerate() {
+            assert_eq!(word, format!("Phillip{}", i));
+            assert_eq!(key, normalize_word(&word));
+            seen += 1;
+        }
+        assert_eq!(seen, count);
+    }
+}
@@ -0,0 +1,390 @@
+/*
+* The English rules, rewritten to work on bytes.
+*
+* Almost every word that reaches the rules is short and ASCII once it has
+* been latinized, and for those the char-based version in lib.rs does a lot of
+* work it doesn't need to: decoding UTF-8, pushing onto growable Strings one
+* char at a time and walking the word once each for lowercasing, the
+* double-letter fold, the trigram fold and the final consonant pass. This
+* version copies the word into a fixed-size buffer on the stack, lowercasing
+* it on the way, applies the start and end of word rules in place and then
+* makes two passes. The first removes double letters and applies the early
+* rules (ought, our, ...). The second runs the trigram rules over that and
+* writes the key, doing the final consonant substitutions (q, x, z, b and d)
+* as each byte is written. That is safe because the final pass maps one byte
+* to one byte and the trigram rules only ever look at their input, never at
+* what they have already written.
+*
+* Anything that isn't ASCII after latinizing, or is too long for the buffers,
+* still goes through the char version, which is also the reference this is
+* tested against.
+*/
+
+// The longest word handled here. The start and end rules can add a byte, and
+// after that the trigram rules can at most double the length of a word (each
+// byte can become two, as c does in ch -> sh).
+const MAX_LEN: usize = 64;
+const CAPACITY: usize = (MAX_LEN + 1) * 2;
+
+/// The key for a word, kept on the stack.
+pub(crate) struct Key {
+    bytes: [u8; CAPACITY],
+    len: usize,
+}
+
+impl Key {
+    pub(crate) fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).expect("the rules only write ASCII")
+    }
+}
+
+/// Run all of the rules over `word`, which must already be latinized, and
+/// return the key. The silent letter rules only run if `silent_letters` is
+/// set. Returns `None` if `word` isn't ASCII or is longer than `MAX_LEN`, in
+/// which case the char version has to be used.
+pub(crate) fn key(word: &str, silent_letters: bool) -> Option<Key> {
+    let input = word.as_bytes();
+    if input.len() > MAX_LEN || !input.is_ascii() {
+        return None;
+    }
+    let mut word = [0; CAPACITY];
+    word[..input.len()].copy_from_slice(input);
+    word[..input.len()].make_ascii_lowercase();
+    let len = edge_rules(&mut word, input.len(), silent_letters);
+    if len < 2 {
+        return Some(Key { bytes: word, len });
+    }
+    Some(fold(&word[..len]))
+}
+
+// The start and end of word rules from `apply_start_rules` and
+// `apply_end_rules`, with the silent letter rules between them as in the
+// char version.
+fn edge_rules(word: &mut [u8; CAPACITY], mut len: usize, silent_letters: bool) -> usize {
+    // **Start of word substitutions**
+    match word[..len] {
+        // s/^cae/se/;
+        [b'c', b'a', b'e', ..] => len = replace_head(word, len, 3, b"se"),
+        // s/^scep/skep/;
+        [b's', b'c', b'e', b'p', ..] => len = replace_head(word, len, 3, b"ske"),
+        // s/^[ao]e/e/;
+        [b'a' | b'o', b'e', ..] => len = replace_head(word, len, 2, b"e"),
+        // s/^c([^eh])/k$1/;, except that ce and ci are soft
+        [b'c', b'e' | b'i', ..] => len = replace_head(word, len, 1, b"s"),
+        [b'c', b'h', ..] => {}
+        [b'c', _, ..] => len = replace_head(word, len, 1, b"k"),
+        // s/^qu/k/;
+        [b'q', b'u', ..] => len = replace_head(word, len, 2, b"k"),
+        // s/^ph/f/;
+        [b'p', b'h', ..] => len = replace_head(word, len, 2, b"f"),
+        // s/^wh/w/;
+        [b'w', b'h', ..] => len = replace_head(word, len, 2, b"w"),
+        // s/^kn/n/;
+        [b'k', b'n', ..] => len = replace_head(word, len, 2, b"n"),
+        // s/^x/z/;
+        [b'x', ..] => len = replace_head(word, len, 1, b"z"),
+        // s/^gn/n/;
+        [b'g', b'n', ..] => len = replace_head(word, len, 2, b"n"),
+        // s/^j/g/;
+        [b'j', ..] => len = replace_head(word, len, 1, b"g"),
+        _ => {}
+    }
+
+    if silent_letters {
+        len = crate::drop_silent_letters(&mut word[..len]);
+    }
+
+    // **End of word substitutions**
+    if let Some((n, replacement)) = crate::american_ending(&word[..len]) {
+        len = replace_tail(word, len, n, replacement.as_bytes());
+    }
+    match word[..len] {
+        // s/ee$/y/;
+        [.., b'e', b'e'] => len = replace_tail(word, len, 2, b"y"),
+        // s/([aeiouy])c$/$1k/;
+        [.., v, b'c'] if is_vowel(v) || v == b'y' => len = replace_tail(word, len, 1, b"k"),
+        // s/[ae]ly$/ly/;
+        [.., b'a' | b'e', b'l', b'y'] => len = replace_tail(word, len, 3, b"ly"),
+        // s/mme$/m/;
+        [.., b'm', b'm', b'e'] => len = replace_tail(word, len, 2, b""),
+        // s/rey$/ray/;
+        [.., b'r', b'e', b'y'] => len = replace_tail(word, len, 2, b"ay"),
+        // s/ey$/y/;
+        [.., _, b'e', b'y'] => len = replace_tail(word, len, 2, b"y"),
+        // s/cy$/sy/;
+        [.., b'c', b'y'] => len = replace_tail(word, len, 2, b"sy"),
+        // s/ed$/d/;
+        [.., b'e', b'd'] => len = replace_tail(word, len, 2, b"d"),
+        // s/([^aeiouy])d$/$1t/;
+        [.., v, b'd'] if !is_vowel(v) && v != b'y' => len = replace_tail(word, len, 1, b"t"),
+        // s/ce$/se/;
+        [.., b'c', b'e'] => len = replace_tail(word, len, 2, b"se"),
+        // s/rine$/ine/;
+        [.., b'r', b'i', b'n', b'e'] => len = replace_tail(word, len, 4, b"rin"),
+        // s/yn$/ine/;
+        [.., b'y', b'n'] => len = replace_tail(word, len, 2, b"in"),
+        // s/ent$/ant/;
+        [.., b'e', b'n', b't'] => len = replace_tail(word, len, 3, b"ant"),
+        // s/ien$/ian/;
+        [.., b'i', b'e', b'n'] => len = replace_tail(word, len, 2, b"an"),
+        // s/ible$/able/;
+        [.., b'i', b'b', b'l', b'e'] => len = replace_tail(word, len, 4, b"able"),
+        // s/ious$/ous/;
+        [.., b'i', b'o', b'u', b's'] => len = replace_tail(word, len, 4, b"ous"),
+        // s/itly$/atly/;
+        [.., b'i', b't', b'l', b'y'] => len = replace_tail(word, len, 4, b"atly"),
+        // s/sean/shawn/g;
+        [.., b's', b'e', b'a', b'n'] => len = replace_tail(word, len, 4, b"shawn"),
+        _ => {}
+    }
+    // s/itly$/atly/;
+    if let [.., b'i', b't', b'l', b'y'] = word[..len] {
+        len = replace_tail(word, len, 4, b"atly");
+    }
+    len
+}
+
+// The double-letter fold, the trigram rules and the final consonant pass, for
+// a word of at least two bytes.
+fn fold(input: &[u8]) -> Key {
+    let first = input[0];
+
+    // Remove double letters, preserving ee and oo, then run the early rules
+    // from `crate::early_rules` as the char version does.
+    let mut mid = [0; CAPACITY];
+    let mut m = 0;
+    for &b in input {
+        if m == 0 || mid[m - 1] != b || b == b'e' || b == b'o' {
+            mid[m] = b;
+            m += 1;
+        }
+    }
+    let mut scratch = [0; CAPACITY];
+    let (word_rules, middle_rules) = crate::early_rules();
+    m = word_rules.apply_bytes_from(&mut mid, m, 0, &mut scratch);
+    m = middle_rules.apply_bytes_from(&mut mid, m, 1, &mut scratch);
+
+    let mut out = [0; CAPACITY];
+    out[0] = first;
+    let mut k = 1;
+    let (mut c1, mut c2) = (b' ', b' ');
+    for &c3 in &mid[1..m] {
+        // Dispatching on the last two bytes first keeps this to a couple of
+        // comparisons per byte. The arms are in the same order as the
+        // trigram rules in lib.rs.
+        match (c2, c3) {
+            // s/([^aeiou])al/$1l/g;
+            (b'a', b'l') => {
+                if c1 == b' ' || is_vowel(c1) {
+                    put(&mut out, &mut k, b'l');
+                } else {
+                    rewrite(&mut out, &mut k, 2, &[c1, b'l']);
+                }
+            }
+            // s/au/ua/g;
+            (b'a', b'u') => rewrite(&mut out, &mut k, 1, b"ua"),
+            // s/ai/ae/g;
+            (b'a', b'i') => put(&mut out, &mut k, b'e'),
+            // s/ae/e/g;
+            (b'a', b'e') => rewrite(&mut out, &mut k, 1, b"e"),
+            // s/an/en/g;
+            (b'a', b'n') => rewrite(&mut out, &mut k, 1, b"en"),
+            // s/ao/oa/g;
+            (b'a', b'o') => rewrite(&mut out, &mut k, 1, b"oa"),
+            // s/igh/i/g;
+            (b'g', b'h') if c1 == b'i' => rewrite(&mut out, &mut k, 1, b""),
+            // s/gh/f/g;
+            (b'g', b'h') => rewrite(&mut out, &mut k, 1, b"f"),
+            // s/gn/n/g;
+            (b'g', b'n') => rewrite(&mut out, &mut k, 1, b"n"),
+            // s/gm/m/g;
+            (b'g', b'm') => rewrite(&mut out, &mut k, 1, b"m"),
+            // s/eu/e/g;
+            (b'e', b'u') => {}
+            // s/ea/ee/g; s/ei/ee/g;
+            (b'e', b'a') | (b'e', b'i') => put(&mut out, &mut k, b'e'),
+            // s/ie/ee/g;
+            (b'i', b'e') => rewrite(&mut out, &mut k, 1, b"ee"),
+            // s/in/en/g;
+            (b'i', b'n') => rewrite(&mut out, &mut k, 1, b"en"),
+            // s/gue/gu/g;
+            (b'u', b'e') if c1 == b'g' => {}
+            // s/ue/e/g;
+            (b'u', b'e') => rewrite(&mut out, &mut k, 1, b"e"),
+            // s/uo/o/g;
+            (b'u', b'o') => rewrite(&mut out, &mut k, 1, b"o"),
+            // s/(mn|nm)/m/g;
+            (b'm', b'n') => {}
+            (b'n', b'm') => rewrite(&mut out, &mut k, 1, b"m"),
+            // s/anc/enc/g;
+            (b'n', b'c') if c1 == b'a' => rewrite(&mut out, &mut k, 2, b"enc"),
+            // s/sc/c/g;
+            (b's', b'c') => rewrite(&mut out, &mut k, 1, b"c"),
+            // s/ou/o/g;
+            (b'o', b'u') => {}
+            // s/ate/ite/g;
+            (b't', b'e') if c1 == b'a' => rewrite(&mut out, &mut k, 2, b"ite"),
+            // s/ph/f/g;
+            (b'p', b'h') => rewrite(&mut out, &mut k, 1, b"f"),
+            // s/y(.)/i$1/g;
+            (b'y', v) => rewrite(&mut out, &mut k, 1, &[b'i', v]),
+            // s/cq/k/g; s/ck/k/g; s/qu/k/g;
+            (b'c', b'q') | (b'c', b'k') | (b'q', b'u') => rewrite(&mut out, &mut k, 1, b"k"),
+            // s/ce/se/g;
+            (b'c', b'e') => rewrite(&mut out, &mut k, 1, b"se"),
+            // s/t[sc]h/sh/g;
+            (b's', b'h') | (b'c', b'h') if c1 == b't' => rewrite(&mut out, &mut k, 2, b"sh"),
+            // s/ch/sh/g;
+            (b'c', b'h') => rewrite(&mut out, &mut k, 1, b"sh"),
+            // s/dg/g/g;
+            (b'd', b'g') => rewrite(&mut out, &mut k, 1, b"g"),
+            // s/ore/or/g;
+            (b'r', b'e') if c1 == b'o' => {}
+            // s/([^sth]+)h/$1/g;
+            (p, b'h') => {
+                if matches!(p, b'c' | b's' | b't')
+                    || (p == b' ' && matches!(first, b'c' | b't' | b's'))
+                {
+                    put(&mut out, &mut k, b'h');
+                }
+            }
+            _ => put(&mut out, &mut k, c3),
+        }
+        c1 = c2;
+        c2 = c3;
+    }
+    Key { bytes: out, len: k }
+}
+
+// Replaces the last `n` bytes of `buf[..len]` and returns the new length.
+fn replace_tail(buf: &mut [u8; CAPACITY], len: usize, n: usize, replacement: &[u8]) -> usize {
+    let start = len.saturating_sub(n);
+    buf[start..start + replacement.len()].copy_from_slice(replacement);
+    start + replacement.len()
+}
+
+// Replaces the first `n` bytes of `buf[..len]` and returns the new length.
+fn replace_head(buf: &mut [u8; CAPACITY], len: usize, n: usize, replacement: &[u8]) -> usize {
+    buf.copy_within(n..len, replacement.len());
+    buf[..replacement.len()].copy_from_slice(replacement);
+    len - n + replacement.len()
+}
+
+// Writes a byte of the key, applying the final consonant pass to it.
+fn put(key: &mut [u8; CAPACITY], k: &mut usize, b: u8) {
+    key[*k] = match b {
+        // s/q/k/g; s/x/k/g;
+        b'q' | b'x' => b'k',
+        // s/z/s/g;
+        b'z' => b's',
+        // s/b/p/g;
+        b'b' => b'p',
+        // s/d/t/g;
+        b'd' => b't',
+        _ => b,
+    };
+    *k += 1;
+}
+
+// Like `replace_tail` for the key, except that it never removes the first
+// byte, which the trigram rules don't own, and goes through `put`.
+fn rewrite(key: &mut [u8; CAPACITY], k: &mut usize, n: usize, replacement: &[u8]) {
+    *k = k.saturating_sub(n).max(1);
+    for &b in replacement {
+        put(key, k, b);
+    }
+}
+
+fn is_vowel(b: u8) -> bool {
+    matches!(b, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+// Fragments that the rules look for, so that the generated words hit
+// them far more often than random letters would.
+const FRAGMENTS: &[&str] = &[
+    "ough", "ought", "plough", "dough", "ight", "eagh", "eaga", "our", "rey", "ur", "ar", "er",
+    "al", "igh", "gh", "eu", "ea", "ei", "ie", "gue", "ue", "au", "ai", "ae", "gn", "mn", "nm",
+    "sc", "ou", "uo", "ate", "ph", "an", "in", "ao", "y", "anc", "gm", "cq", "ck", "qu", "ce",
+    "tsh", "tch", "ch", "sh", "th", "dg", "ore", "h", "ee", "oo", "ll", "ss", "bb", "dd", "x", "z",
+    " ", "'", "-", "0", "ogue", "tre", "bre", "chre", "mmes", "dgement", "oea", "xe", "cae",
+    "scep", "oe", "wr", "rh", "ps", "pn", "mb", "mn", "alk", "olk", "ebt", "oubt", "btl", "ing",
+];
+
+// A small linear congruential generator, so the corpus is the same on
+// every run without pulling in a dependency.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % bound as u64) as usize
+    }
+}
+
+/// Generates `size` words from pieces of the rules' patterns and random
+/// letters, the same on every run. The tests check the two versions of the
+/// rules agree on these, and benches/rules.rs times them.
+pub fn corpus(size: usize) -> Vec<String> {
+    let mut rng = Lcg(0x5eed);
+    let mut words = Vec::with_capacity(size);
+    for _ in 0..size {
+        let mut word = String::new();
+        let pieces = rng.next(7);
+        for _ in 0..pieces {
+            if rng.next(2) == 0 {
+                word.push_str(FRAGMENTS[rng.next(FRAGMENTS.len())]);
+            } else if rng.next(8) == 0 {
+                word.push((b'A' + rng.next(26) as u8) as char);
+            } else {
+                word.push((b'a' + rng.next(26) as u8) as char);
+            }
+        }
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_char_rules_on_corpus() {
+        for word in corpus(200_000) {
+            let key = key(&word, true).expect("the corpus is short and ASCII");
+            assert_eq!(
+                key.as_str(),
+                crate::normalize_word_by_chars(&word),
+                "{:?}",
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn matches_char_rules_without_silent_letters() {
+        let options = crate::Options::new().silent_letters(false);
+        for word in corpus(50_000) {
+            let key = key(&word, false).expect("the corpus is short and ASCII");
+            let mut dest = crate::latinize(&word).into_owned();
+            crate::apply_char_rules(&mut dest, &mut String::new(), false, |_, _| {});
+            assert_eq!(key.as_str(), dest, "{:?}", word);
+            assert_eq!(crate::normalize_word_with(&word, &options), dest);
+        }
+    }
+
+    #[test]
+    fn matches_char_rules_on_whole_words() {
+        for word in corpus(50_000) {
+            assert_eq!(
+                crate::normalize_word(&word),
+                crate::normalize_word_by_chars(&word),
+                "{:?}",
+                word
+            );
+        }
+    }
+}
@@ -0,0 +1,385 @@
+/*
+* Folding of Chinese names across romanization systems.
+*
+* The same surname is written Zhang in Pinyin, Chang in Wade-Giles and Cheung
+* in Hong Kong Cantonese. Wade-Giles without its apostrophes merges several
+* Pinyin initials (ch is both zh and ch, and also j and q before an i), so the
+* shared key can't be more precise than Wade-Giles is. Each syllable is split
+* into an initial and a final, the initial is reduced to a class that covers
+* every spelling of it and the final is rewritten to its Pinyin form.
+*
+* Cantonese spellings don't map onto Mandarin syllable by syllable, so common
+* Cantonese surnames that aren't also valid Mandarin spellings are looked up in
+* a table first.
+*/
+
+// Longest first so that segmentation tries "zh" before "z".
+const INITIALS: &[&str] = &[
+    "zh", "ch", "sh", "hs", "ts", "tz", "dz", "ss", "sz", "b", "p", "m", "f", "d", "t", "n", "l",
+    "g", "k", "h", "j", "q", "x", "r", "z", "c", "s", "y", "w",
+];
+
+// Pinyin finals plus the Wade-Giles spellings (ih, ien, ieh, ung, ...),
+// longest first.
+const FINALS: &[&str] = &[
+    "iang", "iong", "uang", "iung", "ueng", "ang", "eng", "ing", "ong", "ung", "iao", "ian", "ien",
+    "ieh", "uai", "uan", "uei", "ueh", "ai", "ei", "ao", "ou", "an", "en", "in", "un", "ia", "ie",
+    "iu", "ua", "uo", "ui", "ue", "ve", "er", "ih", "eh", "a", "o", "e", "i", "u", "v",
+];
+
+// Hong Kong Cantonese surname spellings and their Pinyin equivalents. Only
+// spellings that aren't also valid Mandarin syllables are listed.
+const CANTONESE_SURNAMES: &[(&str, &str)] = &[
+    ("au", "ou"),
+    ("chau", "zhou"),
+    ("cheuk", "zhuo"),
+    ("cheung", "zhang"),
+    ("choi", "cai"),
+    ("chow", "zhou"),
+    ("fung", "feng"),
+    ("heung", "xiang"),
+    ("ip", "ye"),
+    ("kam", "jin"),
+    ("keung", "jiang"),
+    ("kwan", "guan"),
+    ("kwok", "guo"),
+    ("lam", "lin"),
+    ("lau", "liu"),
+    ("leung", "liang"),
+    ("lui", "lu"),
+    ("mak", "mai"),
+    ("mok", "mo"),
+    ("ng", "wu"),
+    ("poon", "pan"),
+    ("shek", "shi"),
+    ("sit", "xue"),
+    ("siu", "xiao"),
+    ("sze", "shi"),
+    ("tam", "tan"),
+    ("tsang", "zeng"),
+    ("tsoi", "cai"),
+    ("tsui", "xu"),
+    ("wong", "wang"),
+    ("yau", "qiu"),
+    ("yeung", "yang"),
+    ("yip", "ye"),
+    ("yuen", "yuan"),
+];
+
+// The most common Chinese surnames in simplified and traditional characters.
+const HAN_SURNAMES: &[(char, &str)] = &[
+    ('王', "wang"),
+    ('李', "li"),
+    ('张', "zhang"),
+    ('張', "zhang"),
+    ('刘', "liu"),
+    ('劉', "liu"),
+    ('陈', "chen"),
+    ('陳', "chen"),
+    ('杨', "yang"),
+    ('楊', "yang"),
+    ('黄', "huang"),
+    ('黃', "huang"),
+    ('赵', "zhao"),
+    ('趙', "zhao"),
+    ('吴', "wu"),
+    ('吳', "wu"),
+    ('周', "zhou"),
+    ('徐', "xu"),
+    ('孙', "sun"),
+    ('孫', "sun"),
+    ('马', "ma"),
+    ('馬', "ma"),
+    ('朱', "zhu"),
+    ('胡', "hu"),
+    ('郭', "guo"),
+    ('何', "he"),
+    ('高', "gao"),
+    ('林', "lin"),
+    ('罗', "luo"),
+    ('羅', "luo"),
+    ('郑', "zheng"),
+    ('鄭', "zheng"),
+    ('梁', "liang"),
+    ('谢', "xie"),
+    ('謝', "xie"),
+    ('宋', "song"),
+    ('唐', "tang"),
+    ('许', "xu"),
+    ('許', "xu"),
+    ('韩', "han"),
+    ('韓', "han"),
+    ('冯', "feng"),
+    ('馮', "feng"),
+    ('邓', "deng"),
+    ('鄧', "deng"),
+    ('曹', "cao"),
+    ('彭', "peng"),
+    ('曾', "zeng"),
+    ('肖', "xiao"),
+    ('蕭', "xiao"),
+    ('田', "tian"),
+    ('董', "dong"),
+    ('袁', "yuan"),
+    ('潘', "pan"),
+    ('于', "yu"),
+    ('蒋', "jiang"),
+    ('蔣', "jiang"),
+    ('蔡', "cai"),
+    ('余', "yu"),
+    ('杜', "du"),
+    ('叶', "ye"),
+    ('葉', "ye"),
+    ('程', "cheng"),
+    ('苏', "su"),
+    ('蘇', "su"),
+    ('魏', "wei"),
+    ('吕', "lu"),
+    ('呂', "lu"),
+    ('丁', "ding"),
+    ('任', "ren"),
+    ('沈', "shen"),
+    ('姚', "yao"),
+    ('卢', "lu"),
+    ('盧', "lu"),
+    ('姜', "jiang"),
+    ('崔', "cui"),
+    ('钟', "zhong"),
+    ('鍾', "zhong"),
+    ('谭', "tan"),
+    ('譚', "tan"),
+    ('陆', "lu"),
+    ('陸', "lu"),
+    ('汪', "wang"),
+    ('范', "fan"),
+    ('金', "jin"),
+    ('石', "shi"),
+    ('廖', "liao"),
+    ('贾', "jia"),
+    ('賈', "jia"),
+    ('夏', "xia"),
+    ('韦', "wei"),
+    ('韋', "wei"),
+    ('方', "fang"),
+    ('白', "bai"),
+    ('邹', "zou"),
+    ('鄒', "zou"),
+    ('孟', "meng"),
+    ('熊', "xiong"),
+    ('秦', "qin"),
+    ('邱', "qiu"),
+    ('江', "jiang"),
+    ('尹', "yin"),
+    ('薛', "xue"),
+    ('闫', "yan"),
+    ('段', "duan"),
+    ('雷', "lei"),
+    ('侯', "hou"),
+    ('龙', "long"),
+    ('龍', "long"),
+    ('史', "shi"),
+    ('毛', "mao"),
+    ('钱', "qian"),
+    ('錢', "qian"),
+    ('司', "si"),
+];
+
+/// Replace common Chinese surname characters in `text` with their Pinyin.
+/// Other Han characters pass through unchanged, as does everything else.
+pub fn transliterate_han_surnames(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match HAN_SURNAMES.iter().find(|(han, _)| *han == c) {
+            Some((_, pinyin)) => output.push_str(pinyin),
+            None => output.push(c),
+        }
+    }
+    output
+}
+
+/// Fold a latinized, lowercase Chinese name in Pinyin, Wade-Giles or Hong Kong
+/// Cantonese romanization to a key shared by all of them. Hyphens, spaces and
+/// Pinyin apostrophes (Xi'an) separate syllables and are dropped from the key,
+/// as are Wade-Giles aspiration marks (Ch'ien).
+pub(crate) fn fold_chinese_name(word: &str) -> String {
+    let mut cleaned = String::with_capacity(word.len());
+    let mut prev = ' ';
+    for c in word.chars() {
+        match c {
+            '\'' | '\u{2019}' | '\u{02BB}' | '\u{02BC}' => {
+                if matches!(prev, 'a' | 'e' | 'i' | 'o' | 'u' | 'v') {
+                    cleaned.push('-');
+                }
+            }
+            _ => cleaned.push(c),
+        }
+        prev = c;
+    }
+
+    let mut key = String::with_capacity(word.len());
+    let parts = cleaned.split(['-', ' ']).filter(|part| !part.is_empty());
+    for part in parts {
+        let part = match CANTONESE_SURNAMES.iter().find(|(c, _)| *c == part) {
+            Some((_, pinyin)) => pinyin,
+            None => part,
+        };
+        match segment(part) {
+            Some(syllables) => {
+                for (initial, fin) in syllables {
+                    fold_syllable(initial, fin, &mut key);
+                }
+            }
+            None => key.push_str(part),
+        }
+    }
+    key
+}
+
+// Words longer than this aren't segmented, since no name is made of that
+// many syllables.
+const MAX_SEGMENT_LEN: usize = 64;
+
+// Splits a word into (initial, final) pairs, or None if it isn't made up of
+// Mandarin syllables. Initials and finals are tried in the order of their
+// tables, and the first split that covers the whole word wins. Working back
+// from the end, `choice[i]` is the first syllable at byte `i` that is followed
+// by a split of the rest of the word, so each position is only looked at once.
+fn segment(word: &str) -> Option<Vec<(&'static str, &'static str)>> {
+    let bytes = word.as_bytes();
+    if bytes.len() > MAX_SEGMENT_LEN {
+        return None;
+    }
+    let mut choice: Vec<Option<(&str, &str)>> = vec![None; bytes.len() + 1];
+    let splits =
+        |choice: &[Option<(&str, &str)>], i: usize| i == bytes.len() || choice[i].is_some();
+    for i in (0..bytes.len()).rev() {
+        choice[i] = syllables_at(&bytes[i..])
+            .find(|(initial, fin)| splits(&choice, i + initial.len() + fin.len()));
+    }
+    if !bytes.is_empty() && choice[0].is_none() {
+        return None;
+    }
+    let mut syllables = Vec::new();
+    let mut i = 0;
+    while let Some((initial, fin)) = choice.get(i).copied().flatten() {
+        syllables.push((initial, fin));
+        i += initial.len() + fin.len();
+    }
+    Some(syllables)
+}
+
+// The syllables that `rest` starts with, in the order `segment` tries them.
+fn syllables_at(rest: &[u8]) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+    let initials = INITIALS
+        .iter()
+        .copied()
+        .filter(move |i| rest.starts_with(i.as_bytes()))
+        .chain(std::iter::once(""));
+    initials.flat_map(move |initial| {
+        let after = &rest[initial.len()..];
+        FINALS
+            .iter()
+            .copied()
+            .filter(move |f| after.starts_with(f.as_bytes()))
+            // without an initial only finals starting with a, e or o are
+            // syllables on their own
+            .filter(move |f| !initial.is_empty() || f.starts_with(['a', 'e', 'o']))
+            .map(move |fin| (initial, fin))
+    })
+}
+
+fn fold_syllable(initial: &str, fin: &str, key: &mut String) {
+    let palatal = fin.starts_with(['i', 'u', 'v']);
+    let class = match initial {
+        // Wade-Giles ch covers zh, ch, j and q
+        "zh" | "ch" | "q" => "j",
+        // Wade-Giles j is Pinyin r except before i and u where it's Pinyin j
+        "j" if palatal => "j",
+        "j" | "r" => "r",
+        // ts is both Wade-Giles z/c and the Cantonese spelling of x
+        "z" | "c" | "ts" | "tz" | "dz" | "x" | "hs" => "z",
+        "ss" | "sz" => "s",
+        // the aspirated and unaspirated stops only differ by an apostrophe
+        // in Wade-Giles
+        "b" | "p" => "p",
+        "d" | "t" => "t",
+        "g" | "k" => "k",
+        _ => initial,
+    };
+    let fin = match (initial, fin) {
+        // Wade-Giles tzu and ssu are Pinyin zi and si
+        ("tz", "u") | ("ss", "u") | ("sz", "u") => "i",
+        (_, "ih") => "i",
+        (_, "ieh") => "ie",
+        (_, "ien") => "ian",
+        (_, "ueh") | (_, "ve") => "ue",
+        (_, "eh") => "e",
+        (_, "ung") => "ong",
+        (_, "iung") => "iong",
+        (_, "uei") => "ui",
+        // Wade-Giles kuo and lo are Pinyin guo and luo
+        (_, "uo") => "o",
+        (_, "v") => "u",
+        _ => fin,
+    };
+    key.push_str(class);
+    key.push_str(fin);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> String {
+        fold_chinese_name(&name.to_ascii_lowercase())
+    }
+
+    #[test]
+    fn surname_variants() {
+        assert_eq!(key("Zhang"), key("Chang"));
+        assert_eq!(key("Zhang"), key("Cheung"));
+        assert_eq!(key("Xu"), key("Hsu"));
+        assert_eq!(key("Xu"), key("Tsui"));
+        assert_eq!(key("Qian"), key("Chien"));
+        assert_eq!(key("Qian"), key("Ch'ien"));
+        assert_eq!(key("Guo"), key("Kuo"));
+        assert_eq!(key("Guo"), key("Kwok"));
+        assert_eq!(key("Liu"), key("Lau"));
+        assert_eq!(key("Ren"), key("Jen"));
+        assert_eq!(key("Deng"), key("Teng"));
+    }
+
+    #[test]
+    fn multi_syllable_names() {
+        assert_eq!(key("Zedong"), key("Tse-tung"));
+        assert_eq!(key("Xiaoping"), key("Hsiao-p'ing"));
+        assert_eq!(key("Zhongshan"), key("Chung-shan"));
+        assert_eq!(key("Zizhi"), key("Tzu-chih"));
+    }
+
+    #[test]
+    fn different_surnames() {
+        assert_ne!(key("Zhang"), key("Jiang"));
+        assert_ne!(key("Xu"), key("Su"));
+        assert_ne!(key("Qian"), key("Xian"));
+        assert_ne!(key("Wang"), key("Huang"));
+    }
+
+    #[test]
+    fn long_words() {
+        // each "an" splits two ways, so trying every split would never end
+        let word = format!("{}x", "an".repeat(30));
+        assert_eq!(key(&word), word);
+        assert_eq!(key(&"an".repeat(30)), "an".repeat(30));
+        let word = "an".repeat(40);
+        assert_eq!(key(&word), word);
+    }
+
+    #[test]
+    fn han_surnames() {
+        assert_eq!(transliterate_han_surnames("张"), "zhang");
+        assert_eq!(transliterate_han_surnames("張"), "zhang");
+        assert_eq!(transliterate_han_surnames("徐"), "xu");
+        assert_eq!(transliterate_han_surnames("Li"), "Li");
+    }
+}
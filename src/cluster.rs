@@ -0,0 +1,153 @@
+/*
+* Grouping words that share a key.
+*
+* This is for reviewing candidate duplicates: every distinct word is
+* normalized once, the words are gathered by key, and the groups with the
+* most different spellings come first. A word that appears many times only
+* counts once towards the size of its group, so a common name on its own
+* doesn't crowd out the groups that actually have variants.
+*/
+
+use std::collections::HashMap;
+
+use crate::batch::normalize_batch_with;
+use crate::normalizer::Options;
+
+/// The words that normalized to the same key, as returned by
+/// `group_by_key`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    key: String,
+    words: Vec<String>,
+    count: usize,
+}
+
+impl Group {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The distinct words in the group, in the order they first appeared.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// How many words of the input were in the group, counting repeats.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Group `words` by their key with `options`.
+///
+/// The groups are sorted by the number of distinct words in them, largest
+/// first, then by how many times their words appeared and then by key.
+///
+/// ```
+/// use phonetic_normalizer::{group_by_key, Options};
+///
+/// let words = "Smith Smyth Jon Smith John Kathryn".split_whitespace();
+/// let groups = group_by_key(words, &Options::default());
+/// assert_eq!(groups[0].key(), "smith");
+/// assert_eq!(groups[0].words(), ["Smith", "Smyth"]);
+/// assert_eq!(groups[0].count(), 3);
+/// assert_eq!(groups[1].words(), ["Jon", "John"]);
+/// assert_eq!(groups[2].words(), ["Kathryn"]);
+/// ```
+pub fn group_by_key<I>(words: I, options: &Options) -> Vec<Group>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut distinct: Vec<String> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for word in words {
+        let word = word.as_ref();
+        match seen.get(word) {
+            Some(&i) => counts[i] += 1,
+            None => {
+                seen.insert(word.to_string(), distinct.len());
+                distinct.push(word.to_string());
+                counts.push(1);
+            }
+        }
+    }
+
+    let keys = normalize_batch_with(&distinct, options);
+    let mut groups: Vec<Group> = Vec::new();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for ((word, count), key) in distinct.into_iter().zip(counts).zip(keys) {
+        let i = *by_key.entry(key).or_insert_with_key(|key| {
+            groups.push(Group {
+                key: key.clone(),
+                words: Vec::new(),
+                count: 0,
+            });
+            groups.len() - 1
+        });
+        groups[i].words.push(word);
+        groups[i].count += count;
+    }
+
+    groups.sort_by(|a, b| {
+        b.words
+            .len()
+            .cmp(&a.words.len())
+            .then(b.count.cmp(&a.count))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{normalize_word, NameOrigin};
+
+    #[test]
+    fn every_word_in_one_group() {
+        let text = "Catherine Kathryn Katherine Cathryn Phillip Philip Filip color colour \
+                    Smith Smyth Schmidt cat cat cat";
+        let groups = group_by_key(text.split_whitespace(), &Options::default());
+        let mut words: Vec<&str> = groups
+            .iter()
+            .flat_map(|group| group.words().iter().map(String::as_str))
+            .collect();
+        words.sort_unstable();
+        let mut expected: Vec<&str> = text.split_whitespace().collect();
+        expected.sort_unstable();
+        expected.dedup();
+        assert_eq!(words, expected);
+
+        for group in &groups {
+            for word in group.words() {
+                assert_eq!(normalize_word(word), group.key());
+            }
+        }
+        assert_eq!(groups.iter().map(Group::count).sum::<usize>(), 15);
+    }
+
+    #[test]
+    fn sorted_by_size() {
+        let words = [
+            "b", "a", "a", "a", "Phillip", "Philip", "Filip", "Smith", "Smyth",
+        ];
+        let groups = group_by_key(&words, &Options::default());
+        let sizes: Vec<(usize, usize)> = groups
+            .iter()
+            .map(|group| (group.words().len(), group.count()))
+            .collect();
+        assert_eq!(sizes, [(3, 3), (2, 2), (1, 3), (1, 1)]);
+        assert_eq!(groups[0].words(), ["Phillip", "Philip", "Filip"]);
+    }
+
+    #[test]
+    fn uses_options() {
+        let words = ["Mohammed", "Muhammad"];
+        assert_eq!(group_by_key(&words, &Options::default()).len(), 2);
+        let options = Options::new().names(NameOrigin::Arabic);
+        assert_eq!(group_by_key(&words, &options).len(), 1);
+        assert!(group_by_key(Vec::<String>::new(), &options).is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+/*
+* Measuring collisions across a dictionary.
+*
+* The goal of the rules is that words only share a key with other forms of
+* the same word. Given a word list, and optionally a map from each word to
+* its lemma (its dictionary form, so `ran` maps to `run`), this finds every
+* key shared by words of different lemmas. Without a map each word is its own
+* lemma, ignoring case, so any two different words with the same key collide.
+*/
+
+use std::collections::HashMap;
+
+use crate::cluster::group_by_key;
+use crate::normalizer::Options;
+
+/// A key shared by the words of more than one lemma.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collision {
+    key: String,
+    lemmas: Vec<(String, Vec<String>)>,
+}
+
+impl Collision {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The lemmas that share the key, each with its words that have it.
+    pub fn lemmas(&self) -> &[(String, Vec<String>)] {
+        &self.lemmas
+    }
+
+    /// The number of pairs of lemmas that the key wrongly matches.
+    pub fn false_matches(&self) -> usize {
+        let n = self.lemmas.len();
+        n * (n - 1) / 2
+    }
+}
+
+/// The collisions in a word list, as returned by `collisions`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CollisionReport {
+    collisions: Vec<Collision>,
+    words: usize,
+    keys: usize,
+    colliding_words: usize,
+}
+
+impl CollisionReport {
+    /// The collisions, the keys shared by the most lemmas first.
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// The number of distinct words in the list.
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// The number of distinct keys the words normalized to.
+    pub fn keys(&self) -> usize {
+        self.keys
+    }
+
+    /// The number of distinct words that share their key with a word of
+    /// another lemma.
+    pub fn colliding_words(&self) -> usize {
+        self.colliding_words
+    }
+
+    /// The total number of pairs of lemmas that share a key.
+    pub fn false_matches(&self) -> usize {
+        self.collisions.iter().map(Collision::false_matches).sum()
+    }
+
+    /// The fraction of the words that would falsely match a word of another
+    /// lemma, or 0 for an empty list.
+    pub fn false_positive_rate(&self) -> f64 {
+        if self.words == 0 {
+            0.0
+        } else {
+            self.colliding_words as f64 / self.words as f64
+        }
+    }
+}
+
+/// Normalize `words` with `options` and report every key shared by words of
+/// different lemmas. `lemmas` maps words to their lemmas; words missing from
+/// it, or all words if it's `None`, are their own lemma. Words are looked up
+/// and lemmas compared ignoring case.
+///
+/// ```
+/// use phonetic_normalizer::{collisions, Options};
+/// use std::collections::HashMap;
+///
+/// let words = ["colour", "color", "Phillip", "Filip", "ran", "run"];
+/// let report = collisions(&words, None, &Options::default());
+/// assert_eq!(report.collisions().len(), 2);
+/// assert_eq!(report.colliding_words(), 4);
+///
+/// // colour and color are the same word
+/// let lemmas: HashMap<String, String> = HashMap::from([("colour".into(), "color".into())]);
+/// let report = collisions(&words, Some(&lemmas), &Options::default());
+/// assert_eq!(report.collisions().len(), 1);
+/// assert_eq!(report.collisions()[0].key(), "filip");
+/// assert_eq!(report.false_positive_rate(), 2.0 / 6.0);
+/// ```
+pub fn collisions<I>(
+    words: I,
+    lemmas: Option<&HashMap<String, String>>,
+    options: &Options,
+) -> CollisionReport
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let lemmas: Option<HashMap<String, &str>> = lemmas.map(|lemmas| {
+        lemmas
+            .iter()
+            .map(|(word, lemma)| (word.to_lowercase(), lemma.as_str()))
+            .collect()
+    });
+    let lemma_of = |word: &str| {
+        let word = word.to_lowercase();
+        match lemmas.as_ref().and_then(|lemmas| lemmas.get(&word)) {
+            Some(lemma) => lemma.to_lowercase(),
+            None => word,
+        }
+    };
+
+    let groups = group_by_key(words, options);
+    let mut report = CollisionReport {
+        keys: groups.len(),
+        ..CollisionReport::default()
+    };
+    for group in groups {
+        report.words += group.words().len();
+        let mut by_lemma: Vec<(String, Vec<String>)> = Vec::new();
+        for word in group.words() {
+            let lemma = lemma_of(word);
+            match by_lemma.iter_mut().find(|(other, _)| *other == lemma) {
+                Some((_, words)) => words.push(word.clone()),
+                None => by_lemma.push((lemma, vec![word.clone()])),
+            }
+        }
+        if by_lemma.len() > 1 {
+            report.colliding_words += group.words().len();
+            report.collisions.push(Collision {
+                key: group.key().to_string(),
+                lemmas: by_lemma,
+            });
+        }
+    }
+    report.collisions.sort_by(|a, b| {
+        b.lemmas
+            .len()
+            .cmp(&a.lemmas.len())
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lemma_map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(word, lemma)| (word.to_string(), lemma.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn forms_of_one_lemma_dont_collide() {
+        let words = ["Color", "colour", "Colour", "color", "coloured", "COLORED"];
+        let lemmas = lemma_map(&[("colour", "color"), ("Coloured", "colored")]);
+        let report = collisions(&words, Some(&lemmas), &Options::default());
+        assert!(report.collisions().is_empty());
+        assert_eq!(report.words(), 6);
+        assert_eq!(report.false_positive_rate(), 0.0);
+    }
+
+    #[test]
+    fn counts() {
+        // Phillip, Philip and Filip are three lemmas with one key
+        let words = ["Phillip", "Philip", "Filip", "Smith", "Smyth", "Jones"];
+        let report = collisions(&words, None, &Options::default());
+        assert_eq!(report.keys(), 3);
+        assert_eq!(report.collisions().len(), 2);
+        assert_eq!(report.collisions()[0].key(), "filip");
+        assert_eq!(report.collisions()[0].false_matches(), 3);
+        assert_eq!(report.false_matches(), 4);
+        assert_eq!(report.colliding_words(), 5);
+
+        let lemmas = lemma_map(&[("Philip", "Phillip"), ("Filip", "Phillip")]);
+        let report = collisions(&words, Some(&lemmas), &Options::default());
+        assert_eq!(report.collisions().len(), 1);
+        assert_eq!(report.false_matches(), 1);
+    }
+
+    #[test]
+    fn empty_list() {
+        let report = collisions(Vec::<&str>::new(), None, &Options::default());
+        assert_eq!(report, CollisionReport::default());
+        assert_eq!(report.false_positive_rate(), 0.0);
+    }
+}
@@ -0,0 +1,247 @@
+/*
+* Configurations named on the command line with --config.
+*
+* A configuration is a comma separated list of settings for the normalizer:
+* `script=greek`, `names=arabic`, `locale=german`, `rules=FILE`,
+* `exceptions=FILE` and `silent-letters=off`, or `default` for none of them.
+* `dm` stands for Daitch-Mokotoff Soundex instead, where two words match if
+* they share a code. Each configuration is named by its spec, so several can
+* be told apart in a report.
+*/
+
+use std::fs;
+
+use phonetic_normalizer::{
+    daitch_mokotoff, Exceptions, Locale, NameOrigin, Normalizer, Options, Rules, Script,
+};
+
+pub enum Algorithm {
+    Rules(Options),
+    DaitchMokotoff,
+}
+
+/// Tells whether two words match.
+pub type Matcher = Box<dyn FnMut(&str, &str) -> bool>;
+
+pub struct Config {
+    pub name: String,
+    pub algorithm: Algorithm,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            name: "default".to_string(),
+            algorithm: Algorithm::Rules(Options::default()),
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(spec: &str) -> Result<Config, String> {
+        if spec == "dm" || spec == "daitch-mokotoff" {
+            return Ok(Config {
+                name: spec.to_string(),
+                algorithm: Algorithm::DaitchMokotoff,
+            });
+        }
+        let mut options = Options::new();
+        for setting in spec.split(',').map(str::trim) {
+            options = match setting.split_once('=') {
+                None if setting == "default" => options,
+                Some(("script", name)) => options.script(script(name)?),
+                Some(("names", name)) => options.names(name_origin(name)?),
+                Some(("locale", name)) => options.locale(locale(name)?),
+                Some(("rules", path)) => options.rules(read_rules(path)?),
+                Some(("exceptions", path)) => options.exceptions(read_exceptions(path)?),
+                Some(("silent-letters", "on")) => options.silent_letters(true),
+                Some(("silent-letters", "off")) => options.silent_letters(false),
+                _ => return Err(format!("unknown setting `{}` in --config", setting)),
+            };
+        }
+        Ok(Config {
+            name: spec.to_string(),
+            algorithm: Algorithm::Rules(options),
+        })
+    }
+
+    /// The options, for the commands that only work with the rules.
+    pub fn options(&self) -> Result<&Options, String> {
+        match &self.algorithm {
+            Algorithm::Rules(options) => Ok(options),
+            Algorithm::DaitchMokotoff => Err(format!("`{}` only works with eval", self.name)),
+        }
+    }
+
+    /// A function telling whether two words match in this configuration.
+    pub fn matcher(&self) -> Matcher {
+        match &self.algorithm {
+            Algorithm::Rules(options) => {
+                let mut normalizer = Normalizer::new(options.clone());
+                let (mut key1, mut key2) = (String::new(), String::new());
+                Box::new(move |a, b| {
+                    normalizer.normalize_into(a, &mut key1);
+                    normalizer.normalize_into(b, &mut key2);
+                    key1 == key2
+                })
+            }
+            Algorithm::DaitchMokotoff => Box::new(|a: &str, b: &str| {
+                let codes = daitch_mokotoff(b);
+                daitch_mokotoff(a).iter().any(|code| codes.contains(code))
+            }),
+        }
+    }
+}
+
+fn script(name: &str) -> Result<Script, String> {
+    Ok(match name {
+        "greek" => Script::Greek,
+        "arabic" => Script::Arabic,
+        "hebrew" => Script::Hebrew,
+        "han" => Script::Han,
+        "kana" => Script::Kana,
+        "hangul" => Script::Hangul,
+        "devanagari" => Script::Devanagari,
+        _ => return Err(format!("unknown script `{}`", name)),
+    })
+}
+
+fn name_origin(name: &str) -> Result<NameOrigin, String> {
+    Ok(match name {
+        "arabic" => NameOrigin::Arabic,
+        "chinese" => NameOrigin::Chinese,
+        "japanese" => NameOrigin::Japanese,
+        "korean" => NameOrigin::Korean,
+        "indic" => NameOrigin::Indic,
+        _ => return Err(format!("unknown name origin `{}`", name)),
+    })
+}
+
+fn locale(name: &str) -> Result<Locale, String> {
+    Ok(match name {
+        "turkish" => Locale::Turkish,
+        "german" => Locale::German,
+        "danish" => Locale::Danish,
+        "norwegian" => Locale::Norwegian,
+        "vietnamese" => Locale::Vietnamese,
+        _ => return Err(format!("unknown locale `{}`", name)),
+    })
+}
+
+// Reads user rules from `path`: a pattern and its replacement on each line,
+// separated by spaces, with the replacement left out to delete the pattern.
+// Blank lines separate the phases and lines starting with `#` are comments.
+fn read_rules(path: &str) -> Result<Rules, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_rules(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn parse_rules(text: &str) -> Result<Rules, String> {
+    let mut rules = Rules::new();
+    let mut phase = Vec::new();
+    for (number, line) in (1..).zip(text.lines()) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            [] => rules = rules.phase(phase.drain(..)),
+            [first, ..] if first.starts_with('#') => {}
+            [pattern] => phase.push((pattern, "")),
+            [pattern, replacement] => phase.push((pattern, replacement)),
+            _ => {
+                return Err(format!(
+                    "line {}: expected a pattern and a replacement",
+                    number
+                ))
+            }
+        }
+    }
+    Ok(rules.phase(phase))
+}
+
+// Reads exceptions from `path`: a word and the spelling to normalize it as
+// on each line, or `=` and the key to give it. Lines starting with `#` are
+// comments.
+fn read_exceptions(path: &str) -> Result<Exceptions, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_exceptions(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn parse_exceptions(text: &str) -> Result<Exceptions, String> {
+    let mut exceptions = Exceptions::new();
+    for (number, line) in (1..).zip(text.lines()) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        exceptions = match fields[..] {
+            [] => exceptions,
+            [first, ..] if first.starts_with('#') => exceptions,
+            [word, key] if key.len() > 1 && key.starts_with('=') => exceptions.key(word, &key[1..]),
+            [word, spelling] if !spelling.starts_with('=') => exceptions.spelling(word, spelling),
+            _ => {
+                return Err(format!(
+                    "line {}: expected a word and its spelling or =key",
+                    number
+                ))
+            }
+        };
+    }
+    Ok(exceptions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specs() {
+        let config = Config::parse("names=arabic,locale=german").unwrap();
+        let expected = Options::new()
+            .names(NameOrigin::Arabic)
+            .locale(Locale::German);
+        assert_eq!(config.options(), Ok(&expected));
+        assert_eq!(config.name, "names=arabic,locale=german");
+
+        assert_eq!(
+            Config::parse("default").unwrap().options(),
+            Ok(&Options::default())
+        );
+        assert_eq!(
+            Config::parse("silent-letters=off").unwrap().options(),
+            Ok(&Options::new().silent_letters(false))
+        );
+        assert!(Config::parse("silent-letters=maybe").is_err());
+        assert!(Config::parse("dm").unwrap().options().is_err());
+        assert!(Config::parse("names=martian").is_err());
+        assert!(Config::parse("fast").is_err());
+    }
+
+    #[test]
+    fn matchers() {
+        let config = Config::parse("dm").unwrap();
+        let mut matches = config.matcher();
+        assert!(matches("Schwartz", "Szwarc"));
+        assert!(!matches("Schwartz", "Smith"));
+
+        let config = Config::default();
+        let mut matches = config.matcher();
+        assert!(matches("color", "colour"));
+        assert!(!matches("color", "cooler"));
+    }
+
+    #[test]
+    fn rules_files() {
+        let rules = parse_rules("# Dutch\nij y\n\nh\n").unwrap();
+        let expected = Rules::new().phase(vec![("ij", "y")]).phase(vec![("h", "")]);
+        assert_eq!(rules, expected);
+        assert_eq!(parse_rules("\n\n").unwrap(), Rules::new());
+        assert!(parse_rules("a b c\n").is_err());
+    }
+
+    #[test]
+    fn exceptions_files() {
+        let exceptions = parse_exceptions("# Scots\nMenzies mingis\n\nDalziel =dl\n").unwrap();
+        let expected = Exceptions::new()
+            .spelling("menzies", "mingis")
+            .key("dalziel", "dl");
+        assert_eq!(exceptions, expected);
+        assert!(parse_exceptions("Dalziel =\n").is_err());
+        assert!(parse_exceptions("Dalziel\n").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+/*
+* The CSV column mode of the command line tool.
+*
+* The input is read as RFC 4180 CSV a record at a time, so quoted fields can
+* hold commas, doubled quotes and line breaks. The first record is the header.
+* Each selected column is normalized a word at a time and the keys, separated
+* by spaces, go in a new `<column>_phonetic` column at the end of the record.
+* Everything else is copied through unchanged, apart from being quoted only
+* where it needs to be. A record with fewer fields than the header is padded
+* with empty ones, and one with more is an error, since its keys would end up
+* under the wrong headers.
+*/
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use phonetic_normalizer::{Normalizer, Options};
+
+use crate::output::Csv;
+
+/// Reads the records of a CSV file.
+pub struct Reader<R> {
+    reader: BufReader<R>,
+    line: Vec<u8>,
+    number: usize,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(reader: BufReader<R>) -> Reader<R> {
+        Reader {
+            reader,
+            line: Vec::new(),
+            number: 0,
+        }
+    }
+
+    /// The number of the last line read.
+    pub fn line_number(&self) -> usize {
+        self.number
+    }
+
+    /// Whether the next read would have to wait for more input.
+    pub fn is_drained(&self) -> bool {
+        self.reader.buffer().is_empty()
+    }
+
+    /// Read the next record into `record`, returning false at the end of the
+    /// input. Blank lines are skipped.
+    pub fn read_record(&mut self, record: &mut Vec<String>) -> io::Result<bool> {
+        record.clear();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut started = false;
+        loop {
+            self.line.clear();
+            if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+                if quoted {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: unterminated quoted field", self.number),
+                    ));
+                }
+                if started {
+                    record.push(field);
+                }
+                return Ok(started);
+            }
+            self.number += 1;
+            let line = String::from_utf8_lossy(&self.line);
+            if !started && !quoted && line.trim_end_matches(['\r', '\n']).is_empty() {
+                continue;
+            }
+            started = true;
+
+            let mut chars = line.chars().peekable();
+            // whether a quote here would open a quoted field
+            let mut at_start = field.is_empty() && !quoted;
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if quoted => {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                            field.push('"');
+                        } else {
+                            quoted = false;
+                        }
+                    }
+                    _ if quoted => field.push(c),
+                    '"' if at_start => quoted = true,
+                    ',' => {
+                        record.push(std::mem::take(&mut field));
+                        at_start = true;
+                        continue;
+                    }
+                    '\n' => break,
+                    '\r' if chars.peek() == Some(&'\n') => break,
+                    _ => field.push(c),
+                }
+                at_start = false;
+            }
+            if !quoted {
+                record.push(field);
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Writes `record` as a line of CSV.
+pub fn write_record<W: Write, S: AsRef<str>>(writer: &mut W, record: &[S]) -> io::Result<()> {
+    for (i, field) in record.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        write!(writer, "{}", Csv(field.as_ref()))?;
+    }
+    write!(writer, "\r\n")
+}
+
+/// Copies the CSV from `reader` to `writer`, adding a `<column>_phonetic`
+/// column for each of `columns`.
+pub fn add_key_columns<R: Read, W: Write>(
+    mut reader: Reader<R>,
+    mut writer: W,
+    columns: &[String],
+    options: &Options,
+) -> io::Result<()> {
+    let mut record = Vec::new();
+    if !reader.read_record(&mut record)? {
+        return writer.flush();
+    }
+    // a byte order mark would otherwise be part of the first column's name
+    if let Some(name) = record
+        .first_mut()
+        .filter(|name| name.starts_with('\u{FEFF}'))
+    {
+        name.remove(0);
+    }
+    let width = record.len();
+    let indexes = columns
+        .iter()
+        .map(|column| {
+            record
+                .iter()
+                .position(|name| name == column)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("no column named `{}` in the header", column),
+                    )
+                })
+        })
+        .collect::<io::Result<Vec<usize>>>()?;
+    record.extend(columns.iter().map(|column| format!("{}_phonetic", column)));
+    write_record(&mut writer, &record)?;
+
+    let mut normalizer = Normalizer::new(options.clone());
+    let mut key = String::new();
+    while reader.read_record(&mut record)? {
+        // a short record is missing its last cells, which count as empty
+        if record.len() < width {
+            record.resize(width, String::new());
+        }
+        // but a long one would push the keys under the wrong headers
+        if record.len() > width {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "line {}: {} fields, but the header has {}",
+                    reader.line_number(),
+                    record.len(),
+                    width
+                ),
+            ));
+        }
+        for &index in &indexes {
+            let keys = cell_keys(&mut normalizer, &record[index], &mut key);
+            record.push(keys);
+        }
+        write_record(&mut writer, &record)?;
+        if reader.is_drained() {
+            writer.flush()?;
+        }
+    }
+    writer.flush()
+}
+
+// The keys of the words in `cell`, separated by spaces. Words are split at
+// anything but letters, digits and apostrophes, so `Smith-Jones` is two words
+// and `O'Brien` is one.
+fn cell_keys(normalizer: &mut Normalizer, cell: &str, key: &mut String) -> String {
+    let mut keys = String::new();
+    let words = cell
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty());
+    for word in words {
+        normalizer.normalize_into(word, key);
+        if !keys.is_empty() {
+            keys.push(' ');
+        }
+        keys.push_str(key);
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(text: &str) -> io::Result<Vec<Vec<String>>> {
+        let mut reader = Reader::new(BufReader::new(text.as_bytes()));
+        let mut records = Vec::new();
+        let mut record = Vec::new();
+        while reader.read_record(&mut record)? {
+            records.push(record.clone());
+        }
+        Ok(records)
+    }
+
+    #[test]
+    fn reads_rfc_4180() {
+        let text = "a,b,c\r\n\"x, y\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n\n1,,3";
+        assert_eq!(
+            records(text).unwrap(),
+            [
+                vec!["a", "b", "c"],
+                vec!["x, y", "say \"hi\"", "two\nlines"],
+                vec!["1", "", "3"],
+            ]
+        );
+        assert_eq!(records("\"\"\n,\n").unwrap(), [vec![""], vec!["", ""]]);
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        let error = records("a\n\"open,b\nc\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn adds_columns() {
+        let text = "id,last_name,first_name\n1,Walsh,Phillip\n2,\"Mac Donald\",\"Kathryn, Jo\"\n3\n5,Walsh\n4,Smith-Jones,O'Brien\n";
+        let mut output = Vec::new();
+        let columns = ["last_name".to_string(), "first_name".to_string()];
+        let reader = Reader::new(BufReader::new(text.as_bytes()));
+        add_key_columns(reader, &mut output, &columns, &Options::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,last_name,first_name,last_name_phonetic,first_name_phonetic\r\n\
+             1,Walsh,Phillip,walsh,filip\r\n\
+             2,Mac Donald,\"Kathryn, Jo\",mak donlt,kathren go\r\n\
+             3,,,,\r\n\
+             5,Walsh,,walsh,\r\n\
+             4,Smith-Jones,O'Brien,smith gones,o'prien\r\n"
+        );
+    }
+
+    #[test]
+    fn long_record() {
+        let text = "id,name\n1,Walsh\n2,\"Mac\nDonald\",extra\n";
+        let mut output = Vec::new();
+        let reader = Reader::new(BufReader::new(text.as_bytes()));
+        let columns = ["name".to_string()];
+        let error =
+            add_key_columns(reader, &mut output, &columns, &Options::default()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "line 4: 3 fields, but the header has 2");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,name,name_phonetic\r\n1,Walsh,walsh\r\n"
+        );
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let text = "\u{FEFF}id,name\n1,Walsh\n";
+        let mut output = Vec::new();
+        let reader = Reader::new(BufReader::new(text.as_bytes()));
+        add_key_columns(
+            reader,
+            &mut output,
+            &["id".to_string()],
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,name,id_phonetic\r\n1,Walsh,1\r\n"
+        );
+    }
+
+    #[test]
+    fn missing_column() {
+        let reader = Reader::new(BufReader::new("a,b\n1,2\n".as_bytes()));
+        let columns = ["c".to_string()];
+        let error = add_key_columns(reader, io::sink(), &columns, &Options::default()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
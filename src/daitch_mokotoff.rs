@@ -0,0 +1,289 @@
+/*
+* Daitch-Mokotoff Soundex, the standard phonetic code for Jewish and Eastern
+* European genealogy. Unlike the English rules in this crate it produces
+* fixed-length numeric codes, and because some letter combinations have two
+* possible pronunciations (the "ch" in Chaim vs. Charles) a name can have
+* more than one code. Two names are considered a match if they share any code.
+*
+* The rules table is transcribed from the published Daitch-Mokotoff table as
+* used by the Avotaynu and JewishGen implementations.
+*/
+
+use crate::ascii_fold::latinize;
+
+const CODE_LENGTH: usize = 6;
+
+// (pattern, at start of name, before a vowel, anywhere else)
+// Alternate codes are separated by `|` and an empty code means "not coded".
+#[rustfmt::skip]
+const RULES: &[(&str, &str, &str, &str)] = &[
+    ("schtsch", "2", "4", "4"),
+    ("schtsh", "2", "4", "4"),
+    ("schtch", "2", "4", "4"),
+    ("shtch", "2", "4", "4"),
+    ("shtsh", "2", "4", "4"),
+    ("stsch", "2", "4", "4"),
+    ("ttsch", "4", "4", "4"),
+    ("zhdzh", "2", "4", "4"),
+    ("shch", "2", "4", "4"),
+    ("scht", "2", "43", "43"),
+    ("schd", "2", "43", "43"),
+    ("stch", "2", "4", "4"),
+    ("strz", "2", "4", "4"),
+    ("strs", "2", "4", "4"),
+    ("stsh", "2", "4", "4"),
+    ("szcz", "2", "4", "4"),
+    ("szcs", "2", "4", "4"),
+    ("ttch", "4", "4", "4"),
+    ("tsch", "4", "4", "4"),
+    ("ttsz", "4", "4", "4"),
+    ("zdzh", "2", "4", "4"),
+    ("zsch", "4", "4", "4"),
+    ("chs", "5", "54", "54"),
+    ("csz", "4", "4", "4"),
+    ("czs", "4", "4", "4"),
+    ("drz", "4", "4", "4"),
+    ("drs", "4", "4", "4"),
+    ("dsh", "4", "4", "4"),
+    ("dsz", "4", "4", "4"),
+    ("dzh", "4", "4", "4"),
+    ("dzs", "4", "4", "4"),
+    ("sch", "4", "4", "4"),
+    ("sht", "2", "43", "43"),
+    ("szt", "2", "43", "43"),
+    ("shd", "2", "43", "43"),
+    ("szd", "2", "43", "43"),
+    ("tch", "4", "4", "4"),
+    ("trz", "4", "4", "4"),
+    ("trs", "4", "4", "4"),
+    ("tsh", "4", "4", "4"),
+    ("tts", "4", "4", "4"),
+    ("ttz", "4", "4", "4"),
+    ("tzs", "4", "4", "4"),
+    ("tsz", "4", "4", "4"),
+    ("zdz", "2", "4", "4"),
+    ("zhd", "2", "43", "43"),
+    ("zsh", "4", "4", "4"),
+    ("ai", "0", "1", ""),
+    ("aj", "0", "1", ""),
+    ("ay", "0", "1", ""),
+    ("au", "0", "7", ""),
+    ("ch", "5|4", "5|4", "5|4"),
+    ("ck", "5|45", "5|45", "5|45"),
+    ("cz", "4", "4", "4"),
+    ("cs", "4", "4", "4"),
+    ("ds", "4", "4", "4"),
+    ("dz", "4", "4", "4"),
+    ("dt", "3", "3", "3"),
+    ("ei", "0", "1", ""),
+    ("ej", "0", "1", ""),
+    ("ey", "0", "1", ""),
+    ("eu", "1", "1", ""),
+    ("fb", "7", "7", "7"),
+    ("ia", "1", "", ""),
+    ("ie", "1", "", ""),
+    ("io", "1", "", ""),
+    ("iu", "1", "", ""),
+    ("ks", "5", "54", "54"),
+    ("kh", "5", "5", "5"),
+    ("mn", "66", "66", "66"),
+    ("nm", "66", "66", "66"),
+    ("oi", "0", "1", ""),
+    ("oj", "0", "1", ""),
+    ("oy", "0", "1", ""),
+    ("pf", "7", "7", "7"),
+    ("ph", "7", "7", "7"),
+    ("rz", "94|4", "94|4", "94|4"),
+    ("rs", "94|4", "94|4", "94|4"),
+    ("sh", "4", "4", "4"),
+    ("sc", "2", "4", "4"),
+    ("st", "2", "43", "43"),
+    ("sz", "4", "4", "4"),
+    ("sd", "2", "43", "43"),
+    ("th", "3", "3", "3"),
+    ("ts", "4", "4", "4"),
+    ("tc", "4", "4", "4"),
+    ("tz", "4", "4", "4"),
+    ("ui", "0", "1", ""),
+    ("uj", "0", "1", ""),
+    ("uy", "0", "1", ""),
+    ("ue", "0", "", ""),
+    ("zd", "2", "43", "43"),
+    ("zh", "4", "4", "4"),
+    ("zs", "4", "4", "4"),
+    ("a", "0", "", ""),
+    ("b", "7", "7", "7"),
+    ("c", "5|4", "5|4", "5|4"),
+    ("d", "3", "3", "3"),
+    ("e", "0", "", ""),
+    ("f", "7", "7", "7"),
+    ("g", "5", "5", "5"),
+    ("h", "5", "5", ""),
+    ("i", "0", "", ""),
+    ("j", "1|4", "|4", "|4"),
+    ("k", "5", "5", "5"),
+    ("l", "8", "8", "8"),
+    ("m", "6", "6", "6"),
+    ("n", "6", "6", "6"),
+    ("o", "0", "", ""),
+    ("p", "7", "7", "7"),
+    ("q", "5", "5", "5"),
+    ("r", "9", "9", "9"),
+    ("s", "4", "4", "4"),
+    ("t", "3", "3", "3"),
+    ("u", "0", "", ""),
+    ("v", "7", "7", "7"),
+    ("w", "7", "7", "7"),
+    ("x", "5", "54", "54"),
+    ("y", "1", "", ""),
+    ("z", "4", "4", "4"),
+];
+
+// One possible reading of the name so far.
+#[derive(Clone, PartialEq, Eq)]
+struct Branch {
+    code: String,
+    last: Option<&'static str>,
+}
+
+impl Branch {
+    fn push(&mut self, replacement: &'static str, force: bool) {
+        // Adjacent letters with the same code are only coded once, but a
+        // vowel (which has an empty code) in between resets that.
+        let append = match self.last {
+            None => true,
+            Some(last) => !last.ends_with(replacement) || force,
+        };
+        if append && self.code.len() < CODE_LENGTH {
+            self.code.push_str(replacement);
+            self.code.truncate(CODE_LENGTH);
+        }
+        self.last = Some(replacement);
+    }
+}
+
+/// Encode a name with Daitch-Mokotoff Soundex. Returns every possible
+/// six-digit code for the name, or an empty list if it has no letters. The
+/// name is run through `latinize` first and anything other than a letter is
+/// ignored, so "Szwarc", "Schwartz" and "Shvarts" can be compared directly.
+///
+/// ```
+/// use phonetic_normalizer::daitch_mokotoff;
+///
+/// assert_eq!(daitch_mokotoff("Moskowitz"), vec!["645740"]);
+/// assert_eq!(daitch_mokotoff("Peters"), vec!["739400", "734000"]);
+/// ```
+pub fn daitch_mokotoff(name: &str) -> Vec<String> {
+    let mut latin = latinize(name).into_owned();
+    latin.make_ascii_lowercase();
+    let letters: Vec<u8> = latin.bytes().filter(u8::is_ascii_lowercase).collect();
+    if letters.is_empty() {
+        return Vec::new();
+    }
+
+    let mut branches = vec![Branch {
+        code: String::with_capacity(CODE_LENGTH),
+        last: None,
+    }];
+    let mut i = 0;
+    while i < letters.len() {
+        let rest = &letters[i..];
+        let (pattern, at_start, before_vowel, other) = RULES
+            .iter()
+            .find(|(pattern, ..)| rest.starts_with(pattern.as_bytes()))
+            .copied()
+            .expect("every lowercase letter has a rule");
+        let next = i + pattern.len();
+        let codes = if i == 0 {
+            at_start
+        } else if next < letters.len() && is_vowel(letters[next]) {
+            before_vowel
+        } else {
+            other
+        };
+        // m and n next to each other are both coded even though they share
+        // a code.
+        let force = i > 0 && matches!((letters[i - 1], letters[i]), (b'm', b'n') | (b'n', b'm'));
+
+        let mut next_branches: Vec<Branch> = Vec::with_capacity(branches.len());
+        for branch in &branches {
+            for code in codes.split('|') {
+                let mut next_branch = branch.clone();
+                next_branch.push(code, force);
+                if !next_branches.contains(&next_branch) {
+                    next_branches.push(next_branch);
+                }
+            }
+        }
+        branches = next_branches;
+        i = next;
+    }
+
+    let mut codes: Vec<String> = Vec::with_capacity(branches.len());
+    for branch in branches {
+        let mut code = branch.code;
+        while code.len() < CODE_LENGTH {
+            code.push('0');
+        }
+        if !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+fn is_vowel(b: u8) -> bool {
+    matches!(b, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hebrew::transliterate_hebrew;
+
+    fn shares_code(a: &str, b: &str) -> bool {
+        let b = daitch_mokotoff(b);
+        daitch_mokotoff(a).iter().any(|code| b.contains(code))
+    }
+
+    #[test]
+    fn known_codes() {
+        assert_eq!(daitch_mokotoff("Moskowitz"), vec!["645740"]);
+        assert_eq!(daitch_mokotoff("Moskovitz"), vec!["645740"]);
+        assert_eq!(daitch_mokotoff("Peters"), vec!["739400", "734000"]);
+        assert_eq!(daitch_mokotoff("Auerbach"), vec!["097500", "097400"]);
+        assert_eq!(
+            daitch_mokotoff("Jackson"),
+            vec!["154600", "145460", "454600", "445460"]
+        );
+        assert_eq!(daitch_mokotoff("Levy"), vec!["870000"]);
+    }
+
+    #[test]
+    fn spelling_variants_share_a_code() {
+        assert!(shares_code("Schwartz", "Shvarts"));
+        assert!(shares_code("Schwartz", "Szwarc"));
+        assert!(shares_code("Shvarts", "Szwarc"));
+        assert!(shares_code("Rosenberg", "Rozenberg"));
+        assert!(shares_code("Kowalski", "Kovalsky"));
+        assert!(shares_code("Lewinsky", "Levinski"));
+        assert!(!shares_code("Schwartz", "Weiss"));
+        assert!(!shares_code("Cohen", "Levy"));
+    }
+
+    #[test]
+    fn hebrew_names() {
+        assert!(shares_code(&transliterate_hebrew("שוואַרץ"), "Schwartz"));
+        assert!(shares_code(&transliterate_hebrew("רוזנברג"), "Rosenberg"));
+        assert!(shares_code(&transliterate_hebrew("ראָזנבערג"), "Rosenberg"));
+    }
+
+    #[test]
+    fn input_cleanup() {
+        assert_eq!(daitch_mokotoff("Müller"), daitch_mokotoff("Muller"));
+        assert_eq!(daitch_mokotoff("MOSKOWITZ"), daitch_mokotoff("moskowitz"));
+        assert_eq!(daitch_mokotoff("Mos-ko witz"), daitch_mokotoff("Moskowitz"));
+        assert!(daitch_mokotoff("").is_empty());
+        assert!(daitch_mokotoff("123").is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+/*
+* Comparing the keys of a corpus under two versions or configurations.
+*
+* Before stored keys are upgraded it helps to know which of them will
+* change. Each word comes with its old key and its new one, either computed
+* here from two sets of options or read back from the output of an older
+* release. The diff lists the words whose key changed and what that does to
+* matching: words that now share a key but didn't before have gained a match,
+* and words that shared a key but no longer do have lost one. Matches are
+* counted as pairs of words, and listed as the keys that were merged and
+* split, which stays short even when many words are involved.
+*/
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::batch::normalize_batch_with;
+use crate::normalizer::Options;
+
+/// How the keys of a corpus changed, as returned by `diff` and `diff_keys`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyDiff {
+    words: usize,
+    changed: Vec<(String, String, String)>,
+    // the number of words with each old and new key
+    cells: BTreeMap<(String, String), usize>,
+}
+
+impl KeyDiff {
+    /// The number of distinct words compared.
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// The words whose key changed, each with its old and new key, in the
+    /// order they were given.
+    pub fn changed(&self) -> &[(String, String, String)] {
+        &self.changed
+    }
+
+    /// Every old key that changed with the new key it became. An old key
+    /// that appears more than once was split, and the new key of each of its
+    /// words has to be worked out from the word itself.
+    pub fn mapping(&self) -> Vec<(&str, &str)> {
+        self.cells
+            .keys()
+            .filter(|(old, new)| old != new)
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect()
+    }
+
+    /// The new keys now shared by words that had different old keys, each
+    /// with those old keys.
+    pub fn merged(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut by_new: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (old, new) in self.cells.keys() {
+            by_new.entry(new).or_default().push(old);
+        }
+        by_new
+            .into_iter()
+            .filter(|(_, old)| old.len() > 1)
+            .collect()
+    }
+
+    /// The old keys whose words now have different new keys, each with
+    /// those new keys.
+    pub fn split(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut by_old: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (old, new) in self.cells.keys() {
+            by_old.entry(old).or_default().push(new);
+        }
+        by_old
+            .into_iter()
+            .filter(|(_, new)| new.len() > 1)
+            .collect()
+    }
+
+    /// The number of pairs of words that match with the new keys but didn't
+    /// with the old.
+    pub fn matches_gained(&self) -> usize {
+        self.pairs_sharing(|(_, new)| new) - self.pairs_sharing_both()
+    }
+
+    /// The number of pairs of words that matched with the old keys but
+    /// don't with the new.
+    pub fn matches_lost(&self) -> usize {
+        self.pairs_sharing(|(old, _)| old) - self.pairs_sharing_both()
+    }
+
+    // The pairs of words that share the part of their keys picked by `key`.
+    fn pairs_sharing(&self, key: impl Fn(&(String, String)) -> &String) -> usize {
+        let mut sizes: BTreeMap<&String, usize> = BTreeMap::new();
+        for (keys, count) in &self.cells {
+            *sizes.entry(key(keys)).or_default() += count;
+        }
+        sizes.values().map(|&n| pairs(n)).sum()
+    }
+
+    fn pairs_sharing_both(&self) -> usize {
+        self.cells.values().map(|&n| pairs(n)).sum()
+    }
+}
+
+fn pairs(n: usize) -> usize {
+    n * n.saturating_sub(1) / 2
+}
+
+/// Compare the keys of `words` with the `old` and `new` options.
+///
+/// ```
+/// use phonetic_normalizer::{diff, NameOrigin, Options};
+///
+/// let words = ["Mohammed", "Muhammad", "Ahmed", "Smith"];
+/// let new = Options::new().names(NameOrigin::Arabic);
+/// let diff = diff(&words, &Options::default(), &new);
+/// assert_eq!(diff.changed().len(), 3);
+/// assert_eq!(diff.matches_gained(), 1);
+/// assert_eq!(diff.matches_lost(), 0);
+/// ```
+pub fn diff<S: AsRef<str> + Sync>(words: &[S], old: &Options, new: &Options) -> KeyDiff {
+    let old_keys = normalize_batch_with(words, old);
+    let new_keys = normalize_batch_with(words, new);
+    diff_keys(
+        words
+            .iter()
+            .zip(old_keys.into_iter().zip(new_keys))
+            .map(|(word, (old, new))| (word.as_ref(), old, new)),
+    )
+}
+
+/// Compare the old and new keys given with each word, such as keys stored
+/// by an older release alongside the ones this one gives. Only the first
+/// entry for each word is used.
+pub fn diff_keys<I, S>(entries: I) -> KeyDiff
+where
+    I: IntoIterator<Item = (S, String, String)>,
+    S: AsRef<str>,
+{
+    let mut diff = KeyDiff::default();
+    let mut seen = HashSet::new();
+    for (word, old, new) in entries {
+        let word = word.as_ref();
+        if !seen.insert(word.to_string()) {
+            continue;
+        }
+        diff.words += 1;
+        if old != new {
+            diff.changed
+                .push((word.to_string(), old.clone(), new.clone()));
+        }
+        *diff.cells.entry((old, new)).or_default() += 1;
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(rows: &[(&str, &str, &str)]) -> KeyDiff {
+        diff_keys(
+            rows.iter()
+                .map(|(word, old, new)| (*word, old.to_string(), new.to_string())),
+        )
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let words = ["color", "colour", "cooler"];
+        let diff = diff(&words, &Options::default(), &Options::default());
+        assert_eq!(diff.words(), 3);
+        assert!(diff.changed().is_empty());
+        assert!(diff.mapping().is_empty());
+        assert_eq!((diff.matches_gained(), diff.matches_lost()), (0, 0));
+    }
+
+    #[test]
+    fn merges_and_splits() {
+        let diff = entries(&[
+            ("lamb", "lamp", "lam"),
+            ("lam", "lam", "lam"),
+            ("lamp", "lamp", "lamp"),
+            ("comb", "komp", "kom"),
+            ("kom", "kom", "kom"),
+            ("lamb", "ignored", "ignored"),
+        ]);
+        assert_eq!(diff.words(), 5);
+        assert_eq!(diff.changed().len(), 2);
+        assert_eq!(
+            diff.changed()[0],
+            ("lamb".into(), "lamp".into(), "lam".into())
+        );
+        assert_eq!(diff.mapping(), [("komp", "kom"), ("lamp", "lam")]);
+        // lamb now matches lam and comb matches kom, but lamb and lamp part
+        assert_eq!(diff.matches_gained(), 2);
+        assert_eq!(diff.matches_lost(), 1);
+        assert_eq!(
+            diff.merged(),
+            [("kom", vec!["kom", "komp"]), ("lam", vec!["lam", "lamp"])]
+        );
+        assert_eq!(diff.split(), [("lamp", vec!["lam", "lamp"])]);
+    }
+
+    #[test]
+    fn pair_counts() {
+        // three words that all matched now match in pairs of one and two
+        let diff = entries(&[("a", "x", "y"), ("b", "x", "y"), ("c", "x", "z")]);
+        assert_eq!(diff.matches_lost(), 2);
+        assert_eq!(diff.matches_gained(), 0);
+        assert_eq!(diff.mapping(), [("x", "y"), ("x", "z")]);
+    }
+}
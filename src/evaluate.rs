@@ -0,0 +1,303 @@
+/*
+* Measuring the rules against pairs of words labeled by hand.
+*
+* Each pair says whether the two words should normalize to the same key. The
+* pairs are normalized with the given options and the result compared with
+* the label, which is how a change to the rules or the options can be checked
+* against a list of known good and bad matches. `evaluate_with` takes any
+* function that decides whether two words match, so other algorithms can be
+* scored on the same pairs.
+*
+* The pairs from the hand-written match and mismatch tests are in
+* data/pairs.csv, in the format `read_pairs` reads, and a sample of British
+* and American spellings of the same words is in data/uk_us.csv.
+*/
+
+use std::io::{self, BufRead};
+
+use crate::normalizer::{Normalizer, Options};
+
+/// How well the keys agreed with a set of labeled pairs, as returned by
+/// `evaluate`. A positive is a pair that matched.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Evaluation {
+    true_positives: usize,
+    false_positives: usize,
+    true_negatives: usize,
+    false_negatives: usize,
+    failures: Vec<(String, String, bool)>,
+}
+
+impl Evaluation {
+    /// The number of pairs evaluated.
+    pub fn pairs(&self) -> usize {
+        self.correct() + self.failures.len()
+    }
+
+    /// The number of pairs that matched when they should and didn't when
+    /// they shouldn't.
+    pub fn correct(&self) -> usize {
+        self.true_positives + self.true_negatives
+    }
+
+    /// Pairs that should match and did.
+    pub fn true_positives(&self) -> usize {
+        self.true_positives
+    }
+
+    /// Pairs that shouldn't match but did.
+    pub fn false_positives(&self) -> usize {
+        self.false_positives
+    }
+
+    /// Pairs that shouldn't match and didn't.
+    pub fn true_negatives(&self) -> usize {
+        self.true_negatives
+    }
+
+    /// Pairs that should match but didn't.
+    pub fn false_negatives(&self) -> usize {
+        self.false_negatives
+    }
+
+    /// The pairs that were wrong, with their labels, in the order they were
+    /// given.
+    pub fn failures(&self) -> &[(String, String, bool)] {
+        &self.failures
+    }
+
+    /// The fraction of pairs that were correct, or 0 if there were none.
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.correct(), self.pairs())
+    }
+
+    /// The fraction of the pairs that matched that should have, or 0 if
+    /// none matched.
+    pub fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    /// The fraction of the pairs that should match that did, or 0 if none
+    /// should.
+    pub fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+
+    /// The harmonic mean of precision and recall, or 0 if both are 0.
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Normalize each pair of words with `options` and count how many match
+/// exactly when their label says they should.
+///
+/// ```
+/// use phonetic_normalizer::{evaluate, Options};
+///
+/// let pairs = [
+///     ("color", "colour", true),
+///     ("Philip", "Phillip", true),
+///     ("Smith", "Jones", false),
+///     ("cat", "dog", true),
+/// ];
+/// let evaluation = evaluate(&pairs, &Options::default());
+/// assert_eq!(evaluation.correct(), 3);
+/// assert_eq!(evaluation.accuracy(), 0.75);
+/// assert_eq!(evaluation.precision(), 1.0);
+/// assert_eq!(evaluation.recall(), 2.0 / 3.0);
+/// assert_eq!(evaluation.failures(), [("cat".to_string(), "dog".to_string(), true)]);
+/// ```
+pub fn evaluate<S: AsRef<str>>(pairs: &[(S, S, bool)], options: &Options) -> Evaluation {
+    let mut normalizer = Normalizer::new(options.clone());
+    let (mut key1, mut key2) = (String::new(), String::new());
+    evaluate_with(pairs, |word1, word2| {
+        normalizer.normalize_into(word1, &mut key1);
+        normalizer.normalize_into(word2, &mut key2);
+        key1 == key2
+    })
+}
+
+/// Like `evaluate`, but with `matches` deciding whether two words match.
+///
+/// ```
+/// use phonetic_normalizer::{daitch_mokotoff, evaluate_with};
+///
+/// let pairs = [("Schwartz", "Szwarc", true), ("Schwartz", "Smith", false)];
+/// let evaluation = evaluate_with(&pairs, |a, b| {
+///     let codes = daitch_mokotoff(b);
+///     daitch_mokotoff(a).iter().any(|code| codes.contains(code))
+/// });
+/// assert_eq!(evaluation.f1(), 1.0);
+/// ```
+pub fn evaluate_with<S, F>(pairs: &[(S, S, bool)], mut matches: F) -> Evaluation
+where
+    S: AsRef<str>,
+    F: FnMut(&str, &str) -> bool,
+{
+    let mut evaluation = Evaluation::default();
+    for (word1, word2, should_match) in pairs {
+        let (word1, word2) = (word1.as_ref(), word2.as_ref());
+        let matched = matches(word1, word2);
+        match (matched, *should_match) {
+            (true, true) => evaluation.true_positives += 1,
+            (false, false) => evaluation.true_negatives += 1,
+            (true, false) => evaluation.false_positives += 1,
+            (false, true) => evaluation.false_negatives += 1,
+        }
+        if matched != *should_match {
+            let failure = (word1.to_string(), word2.to_string(), *should_match);
+            evaluation.failures.push(failure);
+        }
+    }
+    evaluation
+}
+
+/// Read labeled pairs, one `word1,word2,should_match` on each line. The
+/// label is `true` or `false`, `yes` or `no`, or `1` or `0`. Blank lines,
+/// lines starting with `#` and a header line are skipped.
+pub fn read_pairs<R: BufRead>(reader: R) -> io::Result<Vec<(String, String, bool)>> {
+    let mut pairs = Vec::new();
+    for (number, line) in (1..).zip(reader.lines()) {
+        let line = line?;
+        let fields: Vec<&str> = line.trim().split(',').map(str::trim).collect();
+        let bad_line = |problem| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", number, problem),
+            )
+        };
+        match fields[..] {
+            [""] => {}
+            [first, ..] if first.starts_with('#') => {}
+            [_, _, "should_match"] if number == 1 => {}
+            [word1, word2, label] => {
+                let should_match = match label.to_ascii_lowercase().as_str() {
+                    "true" | "yes" | "1" => true,
+                    "false" | "no" | "0" => false,
+                    _ => return Err(bad_line("expected true or false")),
+                };
+                pairs.push((word1.to_string(), word2.to_string(), should_match));
+            }
+            _ => return Err(bad_line("expected word1,word2,should_match")),
+        }
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NameOrigin, Rules};
+
+    #[test]
+    fn options_change_the_score() {
+        let pairs = [
+            ("Dijkstra", "Dykstra", true),
+            ("Dijkstra", "Dirkstra", false),
+        ];
+        let evaluation = evaluate(&pairs, &Options::default());
+        assert_eq!(evaluation.correct(), 1);
+        assert_eq!(evaluation.false_negatives(), 1);
+
+        let options = Options::new().rules(Rules::new().phase(vec![("ij", "y")]));
+        let evaluation = evaluate(&pairs, &options);
+        assert_eq!(evaluation.correct(), 2);
+        assert_eq!(evaluation.accuracy(), 1.0);
+        assert!(evaluation.failures().is_empty());
+    }
+
+    #[test]
+    fn owned_pairs() {
+        let pairs: Vec<(String, String, bool)> = vec![
+            ("Mohammed".to_string(), "Muhammad".to_string(), true),
+            ("Mohammed".to_string(), "Ahmed".to_string(), false),
+        ];
+        let evaluation = evaluate(&pairs, &Options::new().names(NameOrigin::Arabic));
+        assert_eq!(evaluation.pairs(), 2);
+        assert_eq!(evaluation.correct(), 2);
+    }
+
+    #[test]
+    fn scores() {
+        let pairs = [
+            ("a", "a", true),
+            ("a", "a", true),
+            ("a", "a", false),
+            ("a", "b", true),
+            ("a", "b", false),
+        ];
+        let evaluation = evaluate_with(&pairs, |a, b| a == b);
+        assert_eq!(
+            (
+                evaluation.true_positives(),
+                evaluation.false_positives(),
+                evaluation.true_negatives(),
+                evaluation.false_negatives()
+            ),
+            (2, 1, 1, 1)
+        );
+        assert_eq!(evaluation.precision(), 2.0 / 3.0);
+        assert_eq!(evaluation.recall(), 2.0 / 3.0);
+        assert!((evaluation.f1() - 2.0 / 3.0).abs() < 1e-12);
+        let failures: Vec<(&str, &str, bool)> = evaluation
+            .failures()
+            .iter()
+            .map(|(a, b, label)| (a.as_str(), b.as_str(), *label))
+            .collect();
+        assert_eq!(failures, [("a", "a", false), ("a", "b", true)]);
+    }
+
+    #[test]
+    fn no_pairs() {
+        let pairs: [(&str, &str, bool); 0] = [];
+        let evaluation = evaluate(&pairs, &Options::default());
+        assert_eq!(evaluation, Evaluation::default());
+        assert_eq!(evaluation.accuracy(), 0.0);
+        assert_eq!(evaluation.f1(), 0.0);
+    }
+
+    #[test]
+    fn reading_pairs() {
+        let text = "word1,word2,should_match\n\n# a comment\ncolor, colour, TRUE\nat,ate,0\n";
+        let pairs = read_pairs(text.as_bytes()).unwrap();
+        assert_eq!(
+            pairs,
+            [
+                ("color".to_string(), "colour".to_string(), true),
+                ("at".to_string(), "ate".to_string(), false),
+            ]
+        );
+
+        let error = read_pairs("a,b,true\na,b,maybe\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: expected true or false");
+        assert!(read_pairs("a,b\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn bundled_pairs() {
+        let pairs = read_pairs(include_str!("../data/pairs.csv").as_bytes()).unwrap();
+        assert!(pairs.len() >= 80);
+        let evaluation = evaluate(&pairs, &Options::default());
+        assert_eq!(evaluation.failures(), []);
+    }
+}
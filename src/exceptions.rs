@@ -0,0 +1,251 @@
+/*
+* Words whose spelling has little to do with how they're said.
+*
+* No rule can make "colonel" match "kernel" without breaking every other word
+* with an "olo" in it, so words like that are looked up before the rules run.
+* An exception either gives the word a canonical spelling, which then goes
+* through the rules as usual, or gives it a key outright. The lookup ignores
+* case and is done on the word as the English rules would first see it, so
+* "Siobhán" finds the entry for "siobhan". The user's entries are latinized
+* the same way when they are added.
+*
+* The built-in list covers English words and British place and family names
+* that are known for it, along with Irish given names and the British
+* spellings that no rule turns into their American ones, like "storey" and
+* "draught". It's used unless it's turned off, and the user's own entries are
+* checked before it.
+*/
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::ascii_fold::latinize;
+
+// Spellings and the canonical spellings they are normalized as, sorted by
+// spelling.
+static BUILTIN: &[(&str, &str)] = &[
+    ("ageing", "aging"),
+    ("aluminium", "aluminum"),
+    ("amoeba", "ameba"),
+    ("aoife", "eefa"),
+    ("beauchamp", "beecham"),
+    ("bicester", "bister"),
+    ("boatswain", "bosun"),
+    ("caoimhe", "keeva"),
+    ("cheque", "check"),
+    ("choir", "kwire"),
+    ("cholmondeley", "chumley"),
+    ("colonel", "kernel"),
+    ("coxswain", "coxun"),
+    ("draught", "draft"),
+    ("featherstonehaugh", "fanshaw"),
+    ("foetus", "fetus"),
+    ("gloucester", "gloster"),
+    ("grainne", "grania"),
+    ("greenwich", "grennich"),
+    ("gunwale", "gunnel"),
+    ("leicester", "lester"),
+    ("mainwaring", "mannering"),
+    ("manoeuvre", "maneuver"),
+    ("marjoribanks", "marchbanks"),
+    ("mediaeval", "medieval"),
+    ("menzies", "mingis"),
+    ("niamh", "neeve"),
+    ("norwich", "norrich"),
+    ("pyjamas", "pajamas"),
+    ("quay", "key"),
+    ("saoirse", "seersha"),
+    ("sinead", "shinade"),
+    ("siobhan", "shivaun"),
+    ("storey", "story"),
+    ("tonne", "ton"),
+    ("victuals", "vittles"),
+    ("warwick", "warrick"),
+    ("worcester", "wooster"),
+    ("yacht", "yot"),
+];
+
+/// What an exception does to a word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Exception<'a> {
+    /// Run the rules over this spelling instead.
+    Spelling(&'a str),
+    /// Use this as the key without running the rules.
+    Key(&'a str),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Entry {
+    Spelling(String),
+    Key(String),
+}
+
+/// A dictionary of words that the rules get wrong, checked before the rules
+/// run. It starts with a built-in list of English words and names, like
+/// "colonel", "Worcester" and "Siobhan", and can be extended with `spelling`
+/// and `key`.
+///
+/// ```
+/// use phonetic_normalizer::{words_match, Exceptions, Options};
+///
+/// assert!(words_match("colonel", "kernel", &Options::default()));
+/// assert!(words_match("Siobhán", "Shivaun", &Options::default()));
+///
+/// let exceptions = Exceptions::new()
+///     .spelling("Featherstonhaugh", "fanshawe")
+///     .key("Smith", "smith-family");
+/// let options = Options::new().exceptions(exceptions);
+/// assert!(words_match("Featherstonhaugh", "Fanshawe", &options));
+/// assert!(!words_match("Smith", "Smyth", &options));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exceptions {
+    entries: BTreeMap<String, Entry>,
+    builtin: bool,
+}
+
+impl Default for Exceptions {
+    fn default() -> Exceptions {
+        Exceptions {
+            entries: BTreeMap::new(),
+            builtin: true,
+        }
+    }
+}
+
+impl Exceptions {
+    /// The built-in exceptions and no others.
+    pub fn new() -> Exceptions {
+        Exceptions::default()
+    }
+
+    /// Normalize `word` as if it were spelled `canonical`. The word is
+    /// matched ignoring case and accents.
+    pub fn spelling(mut self, word: &str, canonical: &str) -> Exceptions {
+        let entry = Entry::Spelling(canonical.to_string());
+        self.entries.insert(entry_word(word), entry);
+        self
+    }
+
+    /// Give `word` the key `key`, skipping the rules. The word is matched
+    /// ignoring case and accents.
+    pub fn key(mut self, word: &str, key: &str) -> Exceptions {
+        self.entries
+            .insert(entry_word(word), Entry::Key(key.to_string()));
+        self
+    }
+
+    /// Whether to use the built-in list, which is on by default.
+    pub fn builtin(mut self, enabled: bool) -> Exceptions {
+        self.builtin = enabled;
+        self
+    }
+
+    pub fn uses_builtin(&self) -> bool {
+        self.builtin
+    }
+
+    /// The number of exceptions added to the built-in list.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Finds the exception for `word`, if there is one, looking at the
+    // user's entries first.
+    pub(crate) fn lookup(&self, word: &str) -> Option<Exception<'_>> {
+        if !self.entries.is_empty() {
+            let entry = if word.bytes().any(|b| b.is_ascii_uppercase()) {
+                self.entries.get(&word.to_ascii_lowercase())
+            } else {
+                self.entries.get(word)
+            };
+            match entry {
+                Some(Entry::Spelling(spelling)) => return Some(Exception::Spelling(spelling)),
+                Some(Entry::Key(key)) => return Some(Exception::Key(key)),
+                None => {}
+            }
+        }
+        if !self.builtin {
+            return None;
+        }
+        BUILTIN
+            .binary_search_by(|(spelling, _)| compare_ignoring_case(spelling, word))
+            .ok()
+            .map(|i| Exception::Spelling(BUILTIN[i].1))
+    }
+}
+
+// The word of an entry as `lookup` will see it, latinized like the words the
+// English rules are given.
+fn entry_word(word: &str) -> String {
+    latinize(word).to_ascii_lowercase()
+}
+
+// Compares a lowercase `spelling` with `word` as if `word` were lowercase
+// too, without allocating.
+fn compare_ignoring_case(spelling: &str, word: &str) -> Ordering {
+    spelling
+        .bytes()
+        .cmp(word.bytes().map(|b| b.to_ascii_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_list_is_sorted() {
+        for pair in BUILTIN.windows(2) {
+            assert!(pair[0].0 < pair[1].0, "{} is out of order", pair[1].0);
+        }
+        for (spelling, _) in BUILTIN {
+            assert_eq!(*spelling, spelling.to_ascii_lowercase());
+        }
+    }
+
+    #[test]
+    fn lookup_ignores_case() {
+        let exceptions = Exceptions::new();
+        assert_eq!(
+            exceptions.lookup("WORCESTER"),
+            Some(Exception::Spelling("wooster"))
+        );
+        assert_eq!(
+            exceptions.lookup("colonel"),
+            Some(Exception::Spelling("kernel"))
+        );
+        assert_eq!(exceptions.lookup("colonels"), None);
+        assert_eq!(exceptions.lookup(""), None);
+    }
+
+    #[test]
+    fn user_entries_come_first() {
+        let exceptions = Exceptions::new()
+            .key("Colonel", "kernel")
+            .spelling("Dalziel", "deeyell");
+        assert_eq!(exceptions.len(), 2);
+        assert_eq!(exceptions.lookup("colonel"), Some(Exception::Key("kernel")));
+        assert_eq!(
+            exceptions.lookup("DALZIEL"),
+            Some(Exception::Spelling("deeyell"))
+        );
+
+        // entries are latinized like the words they're matched with
+        let exceptions = exceptions.key("Müller", "zz");
+        assert_eq!(exceptions.lookup("muller"), Some(Exception::Key("zz")));
+        let options = crate::Options::new().exceptions(exceptions.clone());
+        assert_eq!(crate::normalize_word_with("Müller", &options), "zz");
+        assert_eq!(crate::normalize_word_with("MULLER", &options), "zz");
+
+        let exceptions = exceptions.builtin(false);
+        assert_eq!(exceptions.lookup("yacht"), None);
+        assert_eq!(
+            exceptions.lookup("dalziel"),
+            Some(Exception::Spelling("deeyell"))
+        );
+    }
+}
@@ -0,0 +1,202 @@
+/*
+* Tracing how a word becomes its key.
+*
+* `explain` runs the same stages as `normalize_word_with`, but records the
+* word after each of them: the optional transliteration, latinizing, name
+* folding and user rules, then the exceptions and the passes of the English
+* rules. The exceptions only show up when the word has one. The English
+* rules are always run in their char version here, which the tests keep in
+* step with the faster byte version.
+*/
+
+use std::fmt;
+
+use crate::normalizer::Options;
+
+/// One stage of a `Trace`: its name and the word after it ran.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub stage: &'static str,
+    pub output: String,
+}
+
+impl Step {
+    fn new(stage: &'static str, output: &str) -> Step {
+        Step {
+            stage,
+            output: output.to_string(),
+        }
+    }
+}
+
+/// The stages a word went through on its way to its key, as returned by
+/// `explain`.
+///
+/// Displaying a trace gives the word and its key, then a line for each
+/// stage, with a `*` against the ones that changed the word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    word: String,
+    steps: Vec<Step>,
+    // where the English rules start in `steps`
+    english: usize,
+}
+
+impl Trace {
+    /// The word that was normalized.
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// The word as the English rules first saw it: after latinizing and
+    /// whichever of the transliteration, name and user rule stages are
+    /// enabled.
+    pub fn latinized(&self) -> &str {
+        &self.steps[self.english - 1].output
+    }
+
+    /// The key the word normalized to, the output of the last step.
+    pub fn key(&self) -> &str {
+        self.steps.last().map_or(&self.word, |step| &step.output)
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} -> {}", self.word, self.key())?;
+        let mut previous = self.word.as_str();
+        for step in &self.steps {
+            let changed = if step.output == previous { ' ' } else { '*' };
+            writeln!(f, "  {} {:<16} {}", changed, step.stage, step.output)?;
+            previous = &step.output;
+        }
+        Ok(())
+    }
+}
+
+/// Normalize `word` with `options`, recording the word after each stage.
+///
+/// ```
+/// use phonetic_normalizer::{explain, Options};
+///
+/// let trace = explain("Phillip", &Options::default());
+/// assert_eq!(trace.key(), "filip");
+/// let stages: Vec<&str> = trace.steps().iter().map(|step| step.stage).collect();
+/// assert_eq!(stages[..3], ["latinize", "start of word", "silent letters"]);
+/// assert_eq!(trace.steps()[1].output, "fillip");
+/// ```
+pub fn explain(word: &str, options: &Options) -> Trace {
+    let mut steps = Vec::new();
+    let mut dest = options
+        .prepare_traced(word, |stage, output| steps.push(Step::new(stage, output)))
+        .into_owned();
+    let english = steps.len();
+    let exception = crate::apply_exceptions(&mut dest, options.word_exceptions());
+    if exception.is_some() {
+        steps.push(Step::new("exceptions", &dest));
+    }
+    if exception != Some(true) {
+        let silent_letters = options.drops_silent_letters();
+        crate::apply_char_rules(
+            &mut dest,
+            &mut String::new(),
+            silent_letters,
+            |stage, output| steps.push(Step::new(stage, output)),
+        );
+    }
+    Trace {
+        word: word.to_string(),
+        steps,
+        english,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{normalize_word_with, Exceptions, NameOrigin, Rules, Script};
+
+    #[test]
+    fn key_matches_normalize() {
+        let words = [
+            "Phillip",
+            "colour",
+            "Catherine",
+            "Müller",
+            "x",
+            "",
+            "thought",
+            "Γιώργος",
+            "Mohammed",
+            "Colonel",
+            "Wrights",
+        ];
+        let options = [
+            Options::default(),
+            Options::new().script(Script::Greek),
+            Options::new().names(NameOrigin::Arabic),
+            Options::new().rules(Rules::new().phase(vec![("ph", "f")])),
+            Options::new().silent_letters(false),
+        ];
+        for options in &options {
+            for word in &words {
+                assert_eq!(
+                    explain(word, options).key(),
+                    normalize_word_with(word, options),
+                    "{}",
+                    word
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stages_follow_options() {
+        let options = Options::new()
+            .script(Script::Greek)
+            .rules(Rules::new().phase(vec![("ph", "f")]));
+        let trace = explain("Φίλιππος", &options);
+        let stages: Vec<&str> = trace.steps().iter().map(|step| step.stage).collect();
+        assert_eq!(
+            stages,
+            [
+                "transliterate",
+                "latinize",
+                "rules",
+                "start of word",
+                "silent letters",
+                "end of word",
+                "double letters",
+                "middle of word",
+                "final consonants",
+            ]
+        );
+
+        assert_eq!(trace.latinized(), "filippos");
+
+        // a single letter skips the rest of word passes
+        assert_eq!(explain("a", &Options::default()).steps().len(), 4);
+        let options = Options::new().silent_letters(false);
+        assert_eq!(explain("a", &options).steps().len(), 3);
+
+        // an exception that gives a key skips all the passes
+        let options = Options::new().exceptions(Exceptions::new().key("yacht", "yot"));
+        let trace = explain("Yacht", &options);
+        let stages: Vec<&str> = trace.steps().iter().map(|step| step.stage).collect();
+        assert_eq!(stages, ["latinize", "exceptions"]);
+        assert_eq!(trace.key(), "yot");
+    }
+
+    #[test]
+    fn display_marks_changes() {
+        let shown = explain("shack", &Options::default()).to_string();
+        let lines: Vec<&str> = shown.lines().collect();
+        assert_eq!(lines[0], "shack -> shak");
+        assert_eq!(lines[1], "    latinize         shack");
+        assert!(lines.contains(&"  * middle of word   shak"));
+    }
+}
@@ -0,0 +1,247 @@
+/*
+* Greek to Latin transliteration. This follows ELOT 743 (the Greek national
+* standard, also used by UN and Greek passports) with a couple of the informal
+* "Greeklish" choices where they are what people actually write, like `mb` for
+* a word-internal μπ.
+*
+* Anything outside of the Greek and Coptic block is passed through untouched so
+* this can run over mixed text before `latinize`.
+*/
+
+use unicode_normalization::char::is_combining_mark;
+
+/// Transliterate any Greek letters in `text` to Latin letters. Accents and
+/// diaeresis marks are dropped and non-Greek characters pass through as-is.
+pub fn transliterate_greek(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let lower = match to_base_lower(c) {
+            Some(l) => l,
+            None => {
+                output.push(c);
+                i += 1;
+                continue;
+            }
+        };
+        let upper = c.is_uppercase();
+        // Decomposed text has its accents as separate combining marks, which
+        // are skipped over to find the letters around this one.
+        let next_at = skip_marks(&chars, i + 1);
+        let after_at = skip_marks(&chars, next_at + 1);
+        let next = chars.get(next_at).copied();
+        let next_lower = next.and_then(to_base_lower);
+        let after = chars.get(after_at).copied().and_then(to_base_lower);
+        let word_start = chars[..i]
+            .iter()
+            .rev()
+            .find(|&&c| !is_combining_mark(c))
+            .and_then(|&c| to_base_lower(c))
+            .is_none();
+        // A diaeresis on the second letter means the pair is pronounced
+        // separately, so it is never a digraph.
+        let next_has_diaeresis = next.is_some_and(has_diaeresis)
+            || chars
+                .get(next_at + 1..after_at)
+                .is_some_and(|marks| marks.contains(&'\u{0308}'));
+
+        let (latin, consumed) = match (lower, next_lower) {
+            // αυ, ευ and ηυ become v before vowels and voiced consonants
+            // and f before voiceless consonants or at the end of a word.
+            ('α', Some('υ')) | ('ε', Some('υ')) | ('η', Some('υ')) if !next_has_diaeresis => {
+                let vowel = match lower {
+                    'α' => "a",
+                    'ε' => "e",
+                    _ => "i",
+                };
+                let consonant = match after {
+                    Some(a) if is_voiceless(a) => "f",
+                    None => "f",
+                    Some(_) => "v",
+                };
+                (format!("{}{}", vowel, consonant), 2)
+            }
+            ('ο', Some('υ')) if !next_has_diaeresis => ("ou".to_string(), 2),
+            // s/^μπ/b/; and s/μπ/mb/g;
+            ('μ', Some('π')) => {
+                if word_start {
+                    ("b".to_string(), 2)
+                } else {
+                    ("mb".to_string(), 2)
+                }
+            }
+            // s/^ντ/d/; and s/ντ/nt/g;
+            ('ν', Some('τ')) if word_start => ("d".to_string(), 2),
+            // A γ before another velar is a nasal. ELOT keeps γκ as gk except at
+            // the start of a word where it is a plain g.
+            ('γ', Some('γ')) => ("ng".to_string(), 2),
+            ('γ', Some('κ')) => {
+                if word_start {
+                    ("g".to_string(), 2)
+                } else {
+                    ("gk".to_string(), 2)
+                }
+            }
+            ('γ', Some('ξ')) | ('γ', Some('χ')) => ("n".to_string(), 1),
+            _ => (single_letter(lower).to_string(), 1),
+        };
+
+        if upper {
+            // All caps words stay all caps, otherwise only the first letter
+            // of a digraph is capitalized (Θ -> Th).
+            let shouting = next.is_some_and(char::is_uppercase);
+            let mut latin_chars = latin.chars();
+            if let Some(first) = latin_chars.next() {
+                output.push(first.to_ascii_uppercase());
+            }
+            for l in latin_chars {
+                output.push(if shouting { l.to_ascii_uppercase() } else { l });
+            }
+        } else {
+            output.push_str(&latin);
+        }
+        // the accents on the letters used are dropped along with them
+        i = if consumed == 2 { after_at } else { next_at };
+    }
+    output
+}
+
+// The index of the first char at or after `i` that isn't a combining mark.
+fn skip_marks(chars: &[char], mut i: usize) -> usize {
+    while chars.get(i).copied().is_some_and(is_combining_mark) {
+        i += 1;
+    }
+    i
+}
+
+fn single_letter(c: char) -> &'static str {
+    match c {
+        'α' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' => "e",
+        'ζ' => "z",
+        'η' => "i",
+        'θ' => "th",
+        'ι' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' => "o",
+        _ => "",
+    }
+}
+
+// Lowercases a Greek letter and strips any accent or diaeresis from it.
+// Returns None for anything that isn't a Greek letter.
+fn to_base_lower(c: char) -> Option<char> {
+    let base = match c {
+        'ά' | 'Ά' | 'ὰ' | 'ᾶ' => 'α',
+        'έ' | 'Έ' | 'ὲ' => 'ε',
+        'ή' | 'Ή' | 'ὴ' | 'ῆ' => 'η',
+        'ί' | 'Ί' | 'ϊ' | 'Ϊ' | 'ΐ' | 'ὶ' | 'ῖ' => 'ι',
+        'ό' | 'Ό' | 'ὸ' => 'ο',
+        'ύ' | 'Ύ' | 'ϋ' | 'Ϋ' | 'ΰ' | 'ὺ' | 'ῦ' => 'υ',
+        'ώ' | 'Ώ' | 'ὼ' | 'ῶ' => 'ω',
+        'Α'..='Ρ' | 'Σ'..='Ω' => c.to_lowercase().next()?,
+        'α'..='ω' => c,
+        _ => return None,
+    };
+    Some(base)
+}
+
+fn has_diaeresis(c: char) -> bool {
+    matches!(c, 'ϊ' | 'Ϊ' | 'ΐ' | 'ϋ' | 'Ϋ' | 'ΰ')
+}
+
+fn is_voiceless(c: char) -> bool {
+    matches!(c, 'θ' | 'κ' | 'ξ' | 'π' | 'σ' | 'ς' | 'τ' | 'φ' | 'χ' | 'ψ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elot_743_transliterations() {
+        assert_eq!(transliterate_greek("Παπαδόπουλος"), "Papadopoulos");
+        assert_eq!(transliterate_greek("Αθήνα"), "Athina");
+        assert_eq!(transliterate_greek("Θεόδωρος"), "Theodoros");
+        assert_eq!(transliterate_greek("Χρήστος"), "Christos");
+        assert_eq!(transliterate_greek("Ψαράς"), "Psaras");
+        assert_eq!(transliterate_greek("Ξάνθη"), "Xanthi");
+    }
+
+    #[test]
+    fn digraphs() {
+        assert_eq!(transliterate_greek("Ευάγγελος"), "Evangelos");
+        assert_eq!(transliterate_greek("αυτός"), "aftos");
+        assert_eq!(transliterate_greek("Μπάμπης"), "Bambis");
+        assert_eq!(transliterate_greek("Ντίνος"), "Dinos");
+        assert_eq!(transliterate_greek("πέντε"), "pente");
+        assert_eq!(transliterate_greek("Αγγελική"), "Angeliki");
+        assert_eq!(transliterate_greek("γκρεμός"), "gremos");
+        assert_eq!(transliterate_greek("άγκυρα"), "agkyra");
+        // diaeresis breaks up a diphthong
+        assert_eq!(transliterate_greek("Ευφροσύνη"), "Effrosyni");
+        assert_eq!(transliterate_greek("ταΰγετος"), "taygetos");
+    }
+
+    #[test]
+    fn decomposed_accents() {
+        use unicode_normalization::UnicodeNormalization;
+
+        for word in &[
+            "Ευάγγελος",
+            "Αύγουστος",
+            "Εύβοια",
+            "αυτός",
+            "Λάμπρος",
+            "ταΰγετος",
+            "Ευφροσύνη",
+            "ΘΕΟΣ",
+        ] {
+            let decomposed: String = word.nfd().collect();
+            assert_eq!(
+                transliterate_greek(&decomposed),
+                transliterate_greek(word),
+                "{}",
+                word
+            );
+        }
+        assert_eq!(transliterate_greek("Αυ\u{0301}γουστος"), "Avgoustos");
+        assert_eq!(transliterate_greek("Λα\u{0301}μπρος"), "Lambros");
+        assert_eq!(transliterate_greek("e\u{0301}"), "e\u{0301}");
+    }
+
+    #[test]
+    fn capitalization() {
+        assert_eq!(transliterate_greek("ΘΕΟΣ"), "THEOS");
+        assert_eq!(transliterate_greek("Θέος"), "Theos");
+    }
+
+    #[test]
+    fn passes_through_non_greek() {
+        assert_eq!(
+            transliterate_greek("Maria Παπαδοπούλου"),
+            "Maria Papadopoulou"
+        );
+        assert_eq!(transliterate_greek("abc"), "abc");
+        assert_eq!(transliterate_greek(""), "");
+    }
+}
@@ -0,0 +1,250 @@
+/*
+* Hebrew and Yiddish to Latin transliteration.
+*
+* Hebrew is usually written without vowel points (niqqud), so unpointed text
+* gets the most common reading of each letter: bet, kaf and pe are b, k and p
+* at the start of a word and v, kh and f elsewhere, and vav and yod are
+* consonants at the start of a word and vowels in the middle. When points are
+* present they are used instead.
+*
+* Yiddish uses the same alphabet but writes out its vowels: ayin is e, vav is
+* u and bet is always b. A word is read as Yiddish when it has one of the
+* spellings that Hebrew doesn't use, like the double vav or a pointed alef.
+*/
+
+/// Transliterate any Hebrew script in `text` to Latin letters. Other
+/// characters pass through unchanged.
+pub fn transliterate_hebrew(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut yiddish = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let word_start = i == 0 || !is_hebrew(chars[i - 1]);
+        if is_hebrew_letter(c) && word_start {
+            let word_end = chars[i..]
+                .iter()
+                .position(|&c| !is_hebrew(c))
+                .map_or(chars.len(), |end| i + end);
+            yiddish = is_yiddish(&chars[i..word_end]);
+        }
+        if !is_hebrew_letter(c) {
+            if !is_point(c) {
+                output.push(c);
+            }
+            i += 1;
+            continue;
+        }
+
+        // gather up the points that belong to this letter
+        let mut marks_end = i + 1;
+        while marks_end < chars.len() && is_point(chars[marks_end]) {
+            marks_end += 1;
+        }
+        let marks = &chars[i + 1..marks_end];
+        let next = chars.get(marks_end).copied();
+        let word_end = !next.is_some_and(is_hebrew_letter);
+        let dagesh = marks.contains(&'\u{05BC}');
+        let rafe = marks.contains(&'\u{05BF}');
+        let vowel = marks.iter().find_map(|&m| point_vowel(m, yiddish));
+
+        let mut consumed = marks_end;
+        // set when the letter is standing in for a vowel and already wrote it
+        let mut vowel_letter = matches!(c, '\u{05F1}' | '\u{05F2}');
+        let latin = match c {
+            // alef and ayin are silent, but a word starting with one starts
+            // with a vowel
+            'א' | 'ע' if !yiddish && word_start && vowel.is_none() => "a",
+            'א' | 'ע' if !yiddish => "",
+            'א' => "",
+            'ע' => "e",
+            'ב' => {
+                if (yiddish || word_start || dagesh) && !rafe {
+                    "b"
+                } else {
+                    "v"
+                }
+            }
+            'ג' => "g",
+            'ד' => "d",
+            'ה' => "h",
+            'ו' => {
+                if next == Some('ו') {
+                    // Yiddish double vav
+                    consumed = skip_marks(&chars, marks_end + 1);
+                    "v"
+                } else if word_start || (vowel.is_some() && !marks.contains(&'\u{05B9}')) {
+                    // a consonant, with its own vowel if it is pointed
+                    "v"
+                } else {
+                    vowel_letter = true;
+                    if marks.contains(&'\u{05B9}') {
+                        "o"
+                    } else if dagesh || yiddish {
+                        "u"
+                    } else {
+                        "o"
+                    }
+                }
+            }
+            'ז' => "z",
+            'ח' => "kh",
+            'ט' => "t",
+            'י' => {
+                if next == Some('י') {
+                    // Yiddish double yod
+                    let after = skip_marks(&chars, marks_end + 1);
+                    let patah = chars[marks_end + 1..after].contains(&'\u{05B7}');
+                    consumed = after;
+                    if patah {
+                        "ay"
+                    } else {
+                        "ey"
+                    }
+                } else if word_start {
+                    "y"
+                } else {
+                    "i"
+                }
+            }
+            'כ' | 'ך' => {
+                if (word_start && !yiddish || dagesh) && !rafe {
+                    "k"
+                } else {
+                    "kh"
+                }
+            }
+            'ל' => "l",
+            'מ' | 'ם' => "m",
+            'נ' | 'ן' => "n",
+            'ס' => "s",
+            'פ' | 'ף' => {
+                if (word_start || dagesh) && !rafe && !word_end {
+                    "p"
+                } else {
+                    "f"
+                }
+            }
+            'צ' | 'ץ' => "ts",
+            'ק' => "k",
+            'ר' => "r",
+            'ש' => {
+                if marks.contains(&'\u{05C2}') {
+                    // sin dot
+                    "s"
+                } else {
+                    "sh"
+                }
+            }
+            'ת' => "t",
+            '\u{05F0}' => "v",  // װ
+            '\u{05F1}' => "oy", // ױ
+            '\u{05F2}' => {
+                // ײ, or ײַ with a patah
+                if marks.contains(&'\u{05B7}') {
+                    "ay"
+                } else {
+                    "ey"
+                }
+            }
+            _ => "",
+        };
+        output.push_str(latin);
+        if !vowel_letter && consumed == marks_end {
+            if let Some(v) = vowel {
+                output.push_str(v);
+            }
+        }
+        i = consumed;
+    }
+    output
+}
+
+fn skip_marks(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && is_point(chars[i]) {
+        i += 1;
+    }
+    i
+}
+
+fn point_vowel(c: char, yiddish: bool) -> Option<&'static str> {
+    match c {
+        // Yiddish kamatz is an o
+        '\u{05B8}' if yiddish => Some("o"),
+        '\u{05B1}' | '\u{05B5}' | '\u{05B6}' => Some("e"), // hataf segol, tsere, segol
+        '\u{05B2}' | '\u{05B7}' | '\u{05B8}' => Some("a"), // hataf patah, patah, kamatz
+        '\u{05B3}' | '\u{05B9}' | '\u{05BA}' | '\u{05C7}' => Some("o"), // hataf kamatz, holam, kamatz katan
+        '\u{05B4}' => Some("i"),                                        // hiriq
+        '\u{05BB}' => Some("u"),                                        // kubutz
+        _ => None,
+    }
+}
+
+fn is_yiddish(word: &[char]) -> bool {
+    word.windows(2).any(|pair| {
+        matches!(
+            pair,
+            ['ו', 'ו'] | ['י', 'י'] | ['א', '\u{05B7}'] | ['א', '\u{05B8}']
+        )
+    }) || word.iter().any(|c| matches!(c, '\u{05F0}'..='\u{05F2}'))
+}
+
+fn is_hebrew(c: char) -> bool {
+    is_hebrew_letter(c) || is_point(c)
+}
+
+fn is_hebrew_letter(c: char) -> bool {
+    matches!(c, '\u{05D0}'..='\u{05EA}' | '\u{05F0}'..='\u{05F2}')
+}
+
+// Niqqud, cantillation marks and the other points that attach to a letter.
+fn is_point(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0591}'
+            ..='\u{05BD}'
+                | '\u{05BF}'
+                | '\u{05C1}'
+                | '\u{05C2}'
+                | '\u{05C4}'
+                | '\u{05C5}'
+                | '\u{05C7}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpointed_hebrew() {
+        assert_eq!(transliterate_hebrew("אברהם"), "avrhm");
+        assert_eq!(transliterate_hebrew("יוסף"), "yosf");
+        assert_eq!(transliterate_hebrew("כהן"), "khn");
+        assert_eq!(transliterate_hebrew("רוזנברג"), "roznvrg");
+        assert_eq!(transliterate_hebrew("שלום"), "shlom");
+    }
+
+    #[test]
+    fn pointed_hebrew() {
+        assert_eq!(transliterate_hebrew("שָׁלוֹם"), "shalom");
+        assert_eq!(transliterate_hebrew("יִשְׂרָאֵל"), "yisrael");
+        assert_eq!(transliterate_hebrew("דָּוִד"), "david");
+    }
+
+    #[test]
+    fn yiddish() {
+        assert_eq!(transliterate_hebrew("שוואַרץ"), "shvarts");
+        assert_eq!(transliterate_hebrew("װײַנבערג"), "vaynberg");
+        assert_eq!(transliterate_hebrew("ראָזנבערג"), "roznberg");
+        assert_eq!(transliterate_hebrew("כאַץ"), "khats");
+    }
+
+    #[test]
+    fn passes_through_non_hebrew() {
+        assert_eq!(transliterate_hebrew("Cohen כהן"), "Cohen khn");
+        assert_eq!(transliterate_hebrew(""), "");
+    }
+}
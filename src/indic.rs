@@ -0,0 +1,315 @@
+/*
+* Devanagari to Latin transliteration, and folding of the ways Indian names
+* are spelled in Latin letters.
+*
+* The transliteration follows IAST, which agrees with ISO 15919 everywhere
+* except for the vocalic r and l (ṛ and ḷ rather than r̥ and l̥) and the
+* anusvara. Every consonant carries an inherent a unless it is followed by a
+* vowel sign or a virama; the Hindi habit of dropping the final a is left to
+* the name folding.
+*
+* Latin spellings of Indian names follow the sound rather than any standard, so
+* the same name is written "Lakshmi" or "Laxmi", "Srinivasan" or "Shrinivasan"
+* and "Deepak" or "Dipak". The folding collapses the sibilants, the aspirated
+* consonants, v and w and the long vowel spellings onto one form.
+*/
+
+/// Transliterate any Devanagari in `text` to IAST. Other characters pass
+/// through unchanged.
+///
+/// ```
+/// use phonetic_normalizer::transliterate_devanagari;
+///
+/// assert_eq!(transliterate_devanagari("कृष्ण"), "kṛṣṇa");
+/// assert_eq!(transliterate_devanagari("लक्ष्मी"), "lakṣmī");
+/// ```
+pub fn transliterate_devanagari(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        let mut consonant = match consonant(c) {
+            Some(consonant) => consonant,
+            None => {
+                match other(c) {
+                    Some(latin) => output.push_str(latin),
+                    None => output.push(c),
+                }
+                continue;
+            }
+        };
+        if chars.get(i) == Some(&'\u{093C}') {
+            // a nukta marks the Persian and Arabic sounds
+            consonant = nukta(c).unwrap_or(consonant);
+            i += 1;
+        }
+        output.push_str(consonant);
+
+        match chars.get(i).copied() {
+            // the virama removes the inherent vowel
+            Some('\u{094D}') => i += 1,
+            Some(sign) => match vowel_sign(sign) {
+                Some(vowel) => {
+                    output.push_str(vowel);
+                    i += 1;
+                }
+                None => output.push('a'),
+            },
+            None => output.push('a'),
+        }
+    }
+    output
+}
+
+/// Fold a latinized, lowercase Indian name so that the common spelling
+/// variants agree. "Lakshmi" and "Laxmi" both become "laksmi", "Krishna" and
+/// "Krushna" both become "krsn" and "Deepak" becomes "dipak".
+pub(crate) fn fold_indic_name(word: &str) -> String {
+    let word: String = word
+        .chars()
+        .filter(|c| !matches!(c, '\'' | '\u{2019}' | '-'))
+        .collect();
+
+    let folded = word
+        // s/ksh/ks/; s/x/ks/;
+        .replace("ksh", "ks")
+        .replace('x', "ks")
+        // IAST c is the ch of popular spellings, and chh its aspirate
+        .replace("chh", "c")
+        .replace("ch", "c")
+        .replace("sh", "s")
+        .replace('w', "v")
+        .replace("aa", "a")
+        .replace("ee", "i")
+        .replace("ii", "i")
+        .replace("oo", "u")
+        .replace("uu", "u");
+
+    let bytes = folded.as_bytes();
+    let mut output = String::with_capacity(folded.len());
+    for (i, c) in folded.char_indices() {
+        let prev = output.as_bytes().last().copied();
+        // the aspirated consonants: s/([kgcjtdpb])h/$1/g;
+        if c == 'h'
+            && matches!(
+                prev,
+                Some(b'k' | b'g' | b'c' | b'j' | b't' | b'd' | b'p' | b'b')
+            )
+        {
+            continue;
+        }
+        // the vocalic r is written ri or ru between consonants (Krishna,
+        // Krushna, Srinivasan), and just r in IAST
+        if matches!(c, 'i' | 'u')
+            && prev == Some(b'r')
+            && output.len() > 1
+            && is_consonant(output.as_bytes()[output.len() - 2])
+            && bytes.get(i + 1).is_some_and(|n| is_consonant(*n))
+        {
+            continue;
+        }
+        output.push(c);
+    }
+
+    // the inherent a at the end of a word is usually not pronounced (Rama,
+    // Ram) and whether it is written varies
+    if output.len() > 2
+        && output.ends_with('a')
+        && is_consonant(output.as_bytes()[output.len() - 2])
+    {
+        output.pop();
+    }
+    // -eshwar is often added to or dropped from a name (Mahesh, Maheshwar)
+    if output.ends_with("esvar") {
+        output.truncate(output.len() - 3);
+    }
+    output
+}
+
+fn consonant(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'क' => "k",
+        'ख' => "kh",
+        'ग' => "g",
+        'घ' => "gh",
+        'ङ' => "ṅ",
+        'च' => "c",
+        'छ' => "ch",
+        'ज' => "j",
+        'झ' => "jh",
+        'ञ' => "ñ",
+        'ट' => "ṭ",
+        'ठ' => "ṭh",
+        'ड' => "ḍ",
+        'ढ' => "ḍh",
+        'ण' => "ṇ",
+        'त' => "t",
+        'थ' => "th",
+        'द' => "d",
+        'ध' => "dh",
+        'न' | 'ऩ' => "n",
+        'प' => "p",
+        'फ' => "ph",
+        'ब' => "b",
+        'भ' => "bh",
+        'म' => "m",
+        'य' => "y",
+        'र' | 'ऱ' => "r",
+        'ल' => "l",
+        'ळ' | 'ऴ' => "ḷ",
+        'व' => "v",
+        'श' => "ś",
+        'ष' => "ṣ",
+        'स' => "s",
+        'ह' => "h",
+        // the precomposed nukta letters
+        '\u{0958}' => "q",  // क़
+        '\u{0959}' => "kh", // ख़
+        '\u{095A}' => "g",  // ग़
+        '\u{095B}' => "z",  // ज़
+        '\u{095C}' => "ṛ",  // ड़
+        '\u{095D}' => "ṛh", // ढ़
+        '\u{095E}' => "f",  // फ़
+        '\u{095F}' => "y",  // य़
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn nukta(c: char) -> Option<&'static str> {
+    match c {
+        'क' => Some("q"),
+        'ख' => Some("kh"),
+        'ग' => Some("g"),
+        'ज' => Some("z"),
+        'ड' => Some("ṛ"),
+        'ढ' => Some("ṛh"),
+        'फ' => Some("f"),
+        'य' => Some("y"),
+        _ => None,
+    }
+}
+
+fn vowel_sign(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'ा' => "ā",
+        'ि' => "i",
+        'ी' => "ī",
+        'ु' => "u",
+        'ू' => "ū",
+        'ृ' => "ṛ",
+        'ॄ' => "ṝ",
+        'ॢ' => "ḷ",
+        'े' | 'ॅ' => "e",
+        'ै' => "ai",
+        'ो' | 'ॉ' => "o",
+        'ौ' => "au",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+// Independent vowels, the signs that follow a vowel and the digits.
+fn other(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'अ' => "a",
+        'आ' => "ā",
+        'इ' => "i",
+        'ई' => "ī",
+        'उ' => "u",
+        'ऊ' => "ū",
+        'ऋ' => "ṛ",
+        'ॠ' => "ṝ",
+        'ऌ' => "ḷ",
+        'ए' | 'ऍ' => "e",
+        'ऐ' => "ai",
+        'ओ' | 'ऑ' => "o",
+        'औ' => "au",
+        'ं' | 'ँ' => "ṃ",
+        'ः' => "ḥ",
+        'ऽ' => "'",
+        'ॐ' => "oṃ",
+        '।' | '॥' => ".",
+        '०' => "0",
+        '१' => "1",
+        '२' => "2",
+        '३' => "3",
+        '४' => "4",
+        '५' => "5",
+        '६' => "6",
+        '७' => "7",
+        '८' => "8",
+        '९' => "9",
+        // a vowel sign, virama or nukta without a consonant before it
+        '\u{093C}' | '\u{093E}'..='\u{094D}' | '\u{0962}' | '\u{0963}' => "",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn is_consonant(b: u8) -> bool {
+    b.is_ascii_lowercase() && !matches!(b, b'a' | b'e' | b'i' | b'o' | b'u' | b'y')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> String {
+        fold_indic_name(&crate::latinize(name).to_ascii_lowercase())
+    }
+
+    #[test]
+    fn iast() {
+        assert_eq!(transliterate_devanagari("राम"), "rāma");
+        assert_eq!(transliterate_devanagari("कृष्ण"), "kṛṣṇa");
+        assert_eq!(transliterate_devanagari("श्रीनिवासन"), "śrīnivāsana");
+        assert_eq!(transliterate_devanagari("दीपक"), "dīpaka");
+        assert_eq!(transliterate_devanagari("चन्द्र"), "candra");
+        assert_eq!(transliterate_devanagari("हिंदी"), "hiṃdī");
+        assert_eq!(transliterate_devanagari("ज़ाकिर"), "zākira");
+        assert_eq!(transliterate_devanagari("ऋषि"), "ṛṣi");
+        assert_eq!(transliterate_devanagari("Ram राम"), "Ram rāma");
+    }
+
+    #[test]
+    fn latin_variants() {
+        assert_eq!(key("Krishna"), key("Krushna"));
+        assert_eq!(key("Lakshmi"), key("Laxmi"));
+        assert_eq!(key("Srinivasan"), key("Shrinivasan"));
+        assert_eq!(key("Mahesh"), key("Maheshwar"));
+        assert_eq!(key("Deepak"), key("Dipak"));
+        assert_eq!(key("Vishwanath"), key("Vishvanath"));
+        assert_eq!(key("Sunitha"), key("Sunita"));
+        assert_eq!(key("Poonam"), key("Punam"));
+        assert_eq!(key("Ram"), key("Rama"));
+        assert_eq!(key("Chandra"), key("Candra"));
+    }
+
+    #[test]
+    fn devanagari_and_latin_agree() {
+        assert_eq!(key(&transliterate_devanagari("कृष्ण")), key("Krishna"));
+        assert_eq!(key(&transliterate_devanagari("लक्ष्मी")), key("Lakshmi"));
+        assert_eq!(key(&transliterate_devanagari("दीपक")), key("Deepak"));
+        assert_eq!(
+            key(&transliterate_devanagari("श्रीनिवासन")),
+            key("Srinivasan")
+        );
+    }
+
+    #[test]
+    fn other_letters_pass_through() {
+        assert_eq!(fold_indic_name("иван"), "иван");
+        assert_eq!(fold_indic_name("rāma"), "rām");
+    }
+
+    #[test]
+    fn different_names() {
+        assert_ne!(key("Priya"), key("Piya"));
+        assert_ne!(key("Ramesh"), key("Mahesh"));
+        assert_ne!(key("Anil"), key("Anand"));
+    }
+}
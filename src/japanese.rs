@@ -0,0 +1,340 @@
+/*
+* Japanese kana to Hepburn romaji, and folding of the other common ways that
+* Japanese names are romanized.
+*
+* The same name can be written "Ōno", "Ohno", "Oono" or "Ouno" depending on how
+* the long vowel is marked, and "Shinzo", "Sinzo" or "Shinzō" depending on
+* whether Hepburn or Kunrei-shiki was used. Folding maps everything onto
+* Hepburn spellings with long vowels shortened.
+*/
+
+/// Transliterate hiragana and katakana in `text` to Hepburn romaji. Long
+/// vowels are written out as two vowels (おおの and オーノ are both "oono") and
+/// ん is always "n". Other characters pass through unchanged.
+pub fn transliterate_kana(text: &str) -> String {
+    let chars: Vec<char> = text.chars().map(katakana_to_hiragana).collect();
+    let mut output = String::with_capacity(text.len());
+    // set after っ, which doubles the next consonant
+    let mut sokuon = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        let mut romaji = match c {
+            'っ' => {
+                sokuon = true;
+                i += 1;
+                continue;
+            }
+            'ー' => {
+                // the long vowel mark repeats the previous vowel
+                if let Some(vowel) = output.chars().last().filter(|c| is_vowel(*c)) {
+                    output.push(vowel);
+                }
+                i += 1;
+                continue;
+            }
+            'ゃ' | 'ゅ' | 'ょ' | 'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' => {
+                // a small kana on its own, without anything to combine with
+                small_kana(c).unwrap_or_default().to_string()
+            }
+            _ => match kana(c) {
+                Some(romaji) => romaji.to_string(),
+                None => {
+                    output.push(c);
+                    i += 1;
+                    continue;
+                }
+            },
+        };
+        i += 1;
+
+        // きゃ -> kya, しゃ -> sha, ふぁ -> fa, てぃ -> ti
+        if let Some(small) = next.and_then(small_kana) {
+            let stem = romaji.trim_end_matches(is_vowel);
+            if matches!(next, Some('ゃ') | Some('ゅ') | Some('ょ')) {
+                if stem.ends_with("sh") || stem.ends_with("ch") || stem == "j" {
+                    romaji = format!("{}{}", stem, &small[1..]);
+                } else {
+                    romaji = format!("{}{}", stem, small);
+                }
+            } else if stem.is_empty() {
+                // うぃ -> wi
+                romaji = format!("w{}", small);
+            } else {
+                romaji = format!("{}{}", stem, small);
+            }
+            i += 1;
+        }
+
+        if sokuon {
+            // っち is tchi in Hepburn, everything else doubles
+            if romaji.starts_with("ch") {
+                output.push('t');
+            } else if let Some(first) = romaji.chars().next().filter(|c| !is_vowel(*c)) {
+                output.push(first);
+            }
+            sokuon = false;
+        }
+        output.push_str(&romaji);
+    }
+    output
+}
+
+/// Fold a latinized, lowercase Japanese name so that Hepburn, Kunrei-shiki
+/// and the various long vowel spellings agree. "Ohno", "Oono", "Ouno" and
+/// "Ōno" all become "ono", "Tutomu" becomes "tsutomu" and "Shimbashi" becomes
+/// "shinbashi".
+pub(crate) fn fold_japanese_name(word: &str) -> String {
+    let bytes = word.as_bytes();
+    let mut output = String::with_capacity(word.len() + 4);
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        let last = output.as_bytes().last().copied();
+        if is_vowel(b as char) {
+            // s/([aeiou])\1/$1/g; and s/ou/o/g;
+            let long = last == Some(b) || (last == Some(b'o') && b == b'u');
+            if !long || !last.is_some_and(|l| is_vowel(l as char)) {
+                output.push(b as char);
+            }
+            i += 1;
+            continue;
+        }
+        if !b.is_ascii_lowercase() {
+            // copy the whole char, which may be more than one byte
+            let c = word[i..].chars().next().expect("i is on a char boundary");
+            output.push(c);
+            i += c.len_utf8();
+            continue;
+        }
+
+        // s/oh([^aeiouy]|$)/o$1/;
+        if b == b'h'
+            && last == Some(b'o')
+            && !bytes
+                .get(i + 1)
+                .is_some_and(|n| is_vowel(*n as char) || *n == b'y')
+        {
+            i += 1;
+            continue;
+        }
+
+        // collect the consonants up to the next vowel
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_lowercase() && !is_vowel(bytes[i] as char) {
+            i += 1;
+        }
+        let mut onset = &word[start..i];
+        let vowel = match bytes.get(i) {
+            Some(v) if is_vowel(*v as char) => *v,
+            _ => {
+                // no vowel follows, so this can only be a final n
+                output.push_str(onset);
+                continue;
+            }
+        };
+
+        // a moraic n (or m before b, p and m) that ends the previous syllable
+        if onset.len() > 1 && (onset.starts_with('n') || onset.starts_with('m')) {
+            let rest = &onset[1..];
+            let nasal = onset.starts_with('n') && !rest.starts_with('y')
+                || onset.starts_with('m') && rest.starts_with(['b', 'p', 'm']);
+            if nasal {
+                output.push('n');
+                onset = rest;
+            }
+        }
+
+        // a doubled consonant, including Hepburn's tch
+        let geminate = onset.len() > 1
+            && (onset.as_bytes()[0] == onset.as_bytes()[1] || onset.starts_with("tch"));
+        if geminate {
+            onset = &onset[1..];
+        }
+
+        let onset = match (onset, vowel) {
+            ("s", b'i') | ("sy", _) => "sh",
+            ("t", b'i') | ("ty", _) | ("cy", _) => "ch",
+            ("t", b'u') => "ts",
+            ("h", b'u') => "f",
+            ("z", b'i') | ("d", b'i') | ("zy", _) | ("jy", _) | ("dy", _) => "j",
+            ("d", b'u') => "z",
+            _ => onset,
+        };
+        if geminate {
+            output.push(if onset.starts_with("ch") {
+                't'
+            } else {
+                onset.as_bytes()[0] as char
+            });
+        }
+        output.push_str(onset);
+    }
+    output
+}
+
+fn katakana_to_hiragana(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn kana(c: char) -> Option<&'static str> {
+    let romaji = match c {
+        'あ' => "a",
+        'い' => "i",
+        'う' => "u",
+        'え' => "e",
+        'お' => "o",
+        'か' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' => "ke",
+        'こ' => "ko",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'ざ' => "za",
+        'じ' => "ji",
+        'ず' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'だ' => "da",
+        'ぢ' => "ji",
+        'づ' => "zu",
+        'で' => "de",
+        'ど' => "do",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' => "ya",
+        'ゆ' => "yu",
+        'よ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' => "wa",
+        'ゐ' => "i",
+        'ゑ' => "e",
+        'を' => "o",
+        'ん' => "n",
+        'ゔ' => "vu",
+        _ => return None,
+    };
+    Some(romaji)
+}
+
+fn small_kana(c: char) -> Option<&'static str> {
+    match c {
+        'ゃ' => Some("ya"),
+        'ゅ' => Some("yu"),
+        'ょ' => Some("yo"),
+        'ぁ' => Some("a"),
+        'ぃ' => Some("i"),
+        'ぅ' => Some("u"),
+        'ぇ' => Some("e"),
+        'ぉ' => Some("o"),
+        _ => None,
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hiragana_and_katakana() {
+        assert_eq!(transliterate_kana("おおの"), "oono");
+        assert_eq!(transliterate_kana("オーノ"), "oono");
+        assert_eq!(transliterate_kana("とうきょう"), "toukyou");
+        assert_eq!(transliterate_kana("しんじゅく"), "shinjuku");
+        assert_eq!(transliterate_kana("きって"), "kitte");
+        assert_eq!(transliterate_kana("マッチャ"), "matcha");
+        assert_eq!(transliterate_kana("コーヒー"), "koohii");
+        assert_eq!(transliterate_kana("ファン"), "fan");
+        assert_eq!(transliterate_kana("つづき"), "tsuzuki");
+        assert_eq!(transliterate_kana("Sato さとう"), "Sato satou");
+    }
+
+    #[test]
+    fn long_vowels() {
+        for variant in &["ohno", "ono", "oono", "ouno"] {
+            assert_eq!(fold_japanese_name(variant), "ono", "{}", variant);
+        }
+        assert_eq!(fold_japanese_name("yuuki"), "yuki");
+        assert_eq!(fold_japanese_name("itoh"), "ito");
+        assert_eq!(fold_japanese_name("itou"), "ito");
+        assert_eq!(fold_japanese_name("ohashi"), "ohashi");
+    }
+
+    #[test]
+    fn kunrei_to_hepburn() {
+        assert_eq!(fold_japanese_name("tutomu"), "tsutomu");
+        assert_eq!(fold_japanese_name("sinzo"), "shinzo");
+        assert_eq!(fold_japanese_name("hukuda"), "fukuda");
+        assert_eq!(fold_japanese_name("tizuko"), "chizuko");
+        assert_eq!(fold_japanese_name("syouta"), "shota");
+        assert_eq!(fold_japanese_name("zyun"), "jun");
+        assert_eq!(fold_japanese_name("kitti"), "kitchi");
+        assert_eq!(fold_japanese_name("shinzo"), "shinzo");
+    }
+
+    #[test]
+    fn other_letters_pass_through() {
+        assert_eq!(fold_japanese_name("иван"), "иван");
+        assert_eq!(fold_japanese_name("ōno-san"), "ōno-san");
+    }
+
+    #[test]
+    fn syllabic_n() {
+        assert_eq!(fold_japanese_name("shimbashi"), "shinbashi");
+        assert_eq!(fold_japanese_name("homma"), "honma");
+        assert_eq!(fold_japanese_name("namba"), "nanba");
+        assert_eq!(fold_japanese_name("kenichi"), "kenichi");
+    }
+}
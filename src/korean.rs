@@ -0,0 +1,265 @@
+/*
+* Hangul romanization and folding of romanized Korean names.
+*
+* Hangul syllable blocks are decomposed arithmetically into their initial,
+* medial and final jamo and romanized with the Revised Romanization of Korean.
+*
+* Korean names show up in three romanizations: Revised Romanization (Bak,
+* Jeong), McCune-Reischauer (Pak, Chŏng) and the popular spellings people
+* actually use (Park, Chung). The folding collapses the differences between
+* them: aspiration apostrophes, breves and the voiced/voiceless consonant pairs
+* go away, eo and u are merged and the handful of surnames whose popular
+* spelling is irregular (Park, Lee, Rhee) come from a table.
+*/
+
+const SYLLABLE_START: u32 = 0xAC00;
+const SYLLABLE_END: u32 = 0xD7A3;
+
+#[rustfmt::skip]
+const INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p", "h",
+];
+
+#[rustfmt::skip]
+const MEDIALS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+
+// How each final is romanized at the end of a word or before a consonant.
+#[rustfmt::skip]
+const FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+
+// How each final is romanized when the next syllable starts with a vowel and
+// the consonant is carried over (한국어 is hangugeo, not hanguk-eo).
+#[rustfmt::skip]
+const LINKED_FINALS: [&str; 28] = [
+    "", "g", "kk", "ks", "n", "nj", "nh", "d", "r", "lg", "lm", "lb", "ls", "lt", "lp", "lh", "m",
+    "b", "bs", "s", "ss", "ng", "j", "ch", "k", "t", "p", "h",
+];
+
+// Surnames whose common spellings can't be reached by the folding rules,
+// mapped to their Revised Romanization.
+const SURNAMES: &[(&str, &str)] = &[
+    ("park", "bak"),
+    ("pak", "bak"),
+    ("bahk", "bak"),
+    ("lee", "i"),
+    ("yi", "i"),
+    ("rhee", "i"),
+    ("rhie", "i"),
+    ("ri", "i"),
+    ("yee", "i"),
+    ("lim", "im"),
+    ("rim", "im"),
+    ("yim", "im"),
+    ("ryu", "yu"),
+    ("rhyu", "yu"),
+    ("ryoo", "yu"),
+    ("yoo", "yu"),
+    ("roh", "no"),
+    ("ro", "no"),
+    ("rho", "no"),
+    ("hur", "heo"),
+    ("her", "heo"),
+    ("paik", "baek"),
+    ("baik", "baek"),
+];
+
+/// Romanize any Hangul syllables in `text` with the Revised Romanization.
+/// Other characters pass through unchanged.
+///
+/// ```
+/// use phonetic_normalizer::transliterate_hangul;
+///
+/// assert_eq!(transliterate_hangul("박"), "bak");
+/// assert_eq!(transliterate_hangul("한국어"), "hangugeo");
+/// ```
+pub fn transliterate_hangul(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len() * 2);
+
+    for (i, &c) in chars.iter().enumerate() {
+        let (initial, medial, fin) = match decompose(c) {
+            Some(jamo) => jamo,
+            None => {
+                output.push(c);
+                continue;
+            }
+        };
+        // the previous syllable's final already supplied this initial
+        let linked = i > 0
+            && initial == 11
+            && decompose(chars[i - 1]).is_some_and(|(_, _, f)| f != 0 && f != 21);
+        let word_start = i == 0 || decompose(chars[i - 1]).is_none();
+        if !linked {
+            // ㄹ is r before a vowel but l at the start of a word in South
+            // Korea, where it is usually dropped before i and y
+            if initial == 5 && word_start {
+                output.push_str(if MEDIALS[medial].starts_with(['i', 'y']) {
+                    ""
+                } else {
+                    "n"
+                });
+            } else {
+                output.push_str(INITIALS[initial]);
+            }
+        }
+        output.push_str(MEDIALS[medial]);
+
+        let next_is_vowel = chars
+            .get(i + 1)
+            .and_then(|&n| decompose(n))
+            .is_some_and(|(init, _, _)| init == 11);
+        if next_is_vowel && fin != 21 {
+            output.push_str(LINKED_FINALS[fin]);
+        } else if fin == 8
+            && chars
+                .get(i + 1)
+                .and_then(|&n| decompose(n))
+                .is_some_and(|(init, _, _)| init == 5)
+        {
+            // ㄹㄹ is ll
+            output.push('l');
+        } else {
+            output.push_str(FINALS[fin]);
+        }
+    }
+    output
+}
+
+/// Fold a latinized, lowercase Korean name so that its Revised Romanization,
+/// McCune-Reischauer and popular spellings agree.
+pub(crate) fn fold_korean_name(word: &str) -> String {
+    let word: String = word
+        .chars()
+        .filter(|c| !matches!(c, '\'' | '\u{2019}' | '\u{02BC}' | '-'))
+        .collect();
+    let word = match SURNAMES.iter().find(|(spelling, _)| *spelling == word) {
+        Some((_, revised)) => revised.to_string(),
+        None => word,
+    };
+
+    // Vowels first: the popular spelling of ㅓ is u (Sung, Hyun, Chun) and
+    // McCune-Reischauer's ŏ and ŭ have already lost their breve.
+    let folded = word
+        .replace("oo", "u")
+        .replace("wu", "u")
+        .replace("eo", "u")
+        .replace("eu", "u")
+        .replace("ou", "u")
+        .replace("oi", "oe")
+        .replace("ai", "ae")
+        .replace("ee", "i")
+        // McCune-Reischauer ch is Revised j, ch' is Revised ch
+        .replace("sh", "s")
+        .replace("ch", "j");
+
+    let bytes = folded.as_bytes();
+    let mut output = String::with_capacity(folded.len());
+    for (i, c) in folded.char_indices() {
+        let c = match c {
+            // the consonant pairs are only distinguished by position or an
+            // apostrophe in McCune-Reischauer
+            'g' => 'k',
+            'd' => 't',
+            'b' => 'p',
+            'l' => 'r',
+            // an h after a vowel only marks the vowel (Ahn, Sohn, Koh)
+            'h' if i > 0
+                && is_vowel(bytes[i - 1])
+                && !bytes.get(i + 1).is_some_and(|n| is_vowel(*n)) =>
+            {
+                continue
+            }
+            _ => c,
+        };
+        // tense consonants are doubled in Revised Romanization but not in
+        // most popular spellings
+        if !output.ends_with(c) || c.is_ascii() && is_vowel(c as u8) {
+            output.push(c);
+        }
+    }
+    output
+}
+
+// Splits a Hangul syllable into its initial, medial and final jamo indexes.
+fn decompose(c: char) -> Option<(usize, usize, usize)> {
+    let code = c as u32;
+    if !(SYLLABLE_START..=SYLLABLE_END).contains(&code) {
+        return None;
+    }
+    let index = (code - SYLLABLE_START) as usize;
+    Some((index / 588, (index % 588) / 28, index % 28))
+}
+
+fn is_vowel(b: u8) -> bool {
+    matches!(b, b'a' | b'e' | b'i' | b'o' | b'u' | b'w' | b'y')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> String {
+        fold_korean_name(&crate::latinize(name).to_ascii_lowercase())
+    }
+
+    #[test]
+    fn revised_romanization() {
+        assert_eq!(transliterate_hangul("김"), "gim");
+        assert_eq!(transliterate_hangul("이"), "i");
+        assert_eq!(transliterate_hangul("최"), "choe");
+        assert_eq!(transliterate_hangul("정"), "jeong");
+        assert_eq!(transliterate_hangul("서울"), "seoul");
+        assert_eq!(transliterate_hangul("한국어"), "hangugeo");
+        assert_eq!(transliterate_hangul("민준"), "minjun");
+        assert_eq!(transliterate_hangul("류"), "yu");
+        assert_eq!(transliterate_hangul("라"), "na");
+        assert_eq!(transliterate_hangul("Kim 김"), "Kim gim");
+    }
+
+    #[test]
+    fn surname_variants() {
+        assert_eq!(key("Park"), key("Pak"));
+        assert_eq!(key("Park"), key("Bak"));
+        assert_eq!(key("Lee"), key("Yi"));
+        assert_eq!(key("Lee"), key("Rhee"));
+        assert_eq!(key("Choi"), key("Choe"));
+        assert_eq!(key("Kim"), key("Gim"));
+        assert_eq!(key("Jung"), key("Jeong"));
+        assert_eq!(key("Chung"), key("Jeong"));
+        assert_eq!(key("Chŏng"), key("Chong"));
+        assert_eq!(key("Yoon"), key("Yun"));
+        assert_eq!(key("Ahn"), key("An"));
+        assert_eq!(key("Sohn"), key("Son"));
+        assert_eq!(key("Moon"), key("Mun"));
+        assert_eq!(key("Suh"), key("Seo"));
+        assert_eq!(key("Kwon"), key("Gwon"));
+    }
+
+    #[test]
+    fn given_names() {
+        assert_eq!(key("Min-jun"), key("Minjun"));
+        assert_eq!(key("Sung-min"), key("Seongmin"));
+        assert_eq!(key("Hyun-woo"), key("Hyeonu"));
+        assert_eq!(key("Ji-hoon"), key("Jihun"));
+        assert_eq!(key("Young-ho"), key("Yeongho"));
+    }
+
+    #[test]
+    fn other_letters_pass_through() {
+        assert_eq!(fold_korean_name("иван"), "иван");
+        assert_eq!(fold_korean_name("kimß"), "kimß");
+    }
+
+    #[test]
+    fn different_names() {
+        assert_ne!(key("Kim"), key("Kang"));
+        assert_ne!(key("Park"), key("Baek"));
+        assert_ne!(key("Lee"), key("Im"));
+    }
+}
@@ -1,19 +1,228 @@
 use std::borrow::Cow;
+use std::sync::OnceLock;
 
+mod arabic;
 mod ascii_fold;
-pub use ascii_fold::latinize;
+mod batch;
+mod byte_rules;
+mod chinese;
+mod cluster;
+mod collisions;
+mod daitch_mokotoff;
+mod diff;
+mod evaluate;
+mod exceptions;
+mod explain;
+mod greek;
+mod hebrew;
+mod indic;
+mod japanese;
+mod korean;
+mod normalizer;
+mod rules;
+pub use arabic::{normalize_arabic_name, transliterate_arabic};
+pub use ascii_fold::{latinize, latinize_with, Locale};
+pub use batch::{normalize_batch, normalize_batch_with, normalize_iter, NormalizeIter};
+pub use chinese::transliterate_han_surnames;
+pub use cluster::{group_by_key, Group};
+pub use collisions::{collisions, Collision, CollisionReport};
+pub use daitch_mokotoff::daitch_mokotoff;
+pub use diff::{diff, diff_keys, KeyDiff};
+pub use evaluate::{evaluate, evaluate_with, read_pairs, Evaluation};
+pub use exceptions::Exceptions;
+pub use explain::{explain, Step, Trace};
+pub use greek::transliterate_greek;
+pub use hebrew::transliterate_hebrew;
+pub use indic::transliterate_devanagari;
+pub use japanese::transliterate_kana;
+pub use korean::transliterate_hangul;
+pub use normalizer::{NameOrigin, Normalizer, Options, Script};
+pub use rules::Rules;
+
+/// The version of the rules. It goes up whenever a change to the rules
+/// changes the key of some word, so keys stored with one version can be
+/// recomputed when it no longer matches.
+pub const ALGORITHM_VERSION: u32 = 5;
+
+pub fn normalize_word(source: &str) -> Cow<'_, str> {
+    let options = Options::default();
+    if options.word_exceptions().lookup(source).is_some() {
+        return normalize_latinized(source, ascii_fold::latinize(source), &options);
+    }
+    match byte_rules::key(source, true) {
+        Some(key) if key.as_str() == source => Cow::Borrowed(source),
+        Some(key) => Cow::Owned(key.as_str().to_owned()),
+        None => normalize_latinized(source, ascii_fold::latinize(source), &options),
+    }
+}
+
+/// Like `normalize_word`, but first runs any stages enabled in `options`,
+/// such as transliterating Greek text to Latin letters.
+pub fn normalize_word_with<'a>(source: &'a str, options: &Options) -> Cow<'a, str> {
+    normalize_latinized(source, options.prepare(source), options)
+}
+
+/// Whether `a` and `b` normalize to the same key with `options`.
+///
+/// ```
+/// use phonetic_normalizer::{words_match, Options};
+///
+/// assert!(words_match("Catherine", "Kathryn", &Options::default()));
+/// assert!(!words_match("Catherine", "Caroline", &Options::default()));
+/// ```
+pub fn words_match(a: &str, b: &str, options: &Options) -> bool {
+    normalize_word_with(a, options) == normalize_word_with(b, options)
+}
+
+/// Like `normalize_word`, but writes the result into `dest`, replacing what
+/// was there. Reusing `dest` across calls saves allocating a new `String` for
+/// every word; `Normalizer::normalize_into` also reuses its scratch space.
+pub fn normalize_into(source: &str, dest: &mut String) {
+    dest.clear();
+    ascii_fold::latinize_into(source, dest);
+    apply_rules(dest, &mut String::new(), &Options::default());
+}
+
+// Hooks for benches/rules.rs, which times the byte and char versions of the
+// English rules against each other. Not part of the API.
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::byte_rules::corpus;
+
+    /// Writes the key for `word` into `dest` with the byte version of the
+    /// rules. Returns false if the word is one it can't handle.
+    pub fn byte_rules(word: &str, dest: &mut String) -> bool {
+        match crate::byte_rules::key(word, true) {
+            Some(key) => {
+                dest.clear();
+                dest.push_str(key.as_str());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes the key for `word` into `dest` with the char version of the
+    /// rules.
+    pub fn char_rules(word: &str, dest: &mut String, scratch: &mut String) {
+        dest.clear();
+        dest.push_str(word);
+        crate::apply_char_rules(dest, scratch, true, |_, _| {});
+    }
+}
 
-pub fn normalize_word(source: &str) -> Cow<str> {
-    let mut dest = ascii_fold::latinize(source);
+// Runs the English rules over `latin`, which must already be latinized.
+// `source` is only used to decide whether the result can borrow the input.
+fn normalize_latinized<'a>(
+    source: &'a str,
+    latin: Cow<'_, str>,
+    options: &Options,
+) -> Cow<'a, str> {
+    let mut dest = latin.into_owned();
+    let mut scratch = String::with_capacity(dest.len());
+    apply_rules(&mut dest, &mut scratch, options);
+    if source == dest {
+        Cow::Borrowed(source)
+    } else {
+        Cow::Owned(dest)
+    }
+}
+
+// Rewrites `dest` in place with the exceptions and rule groups in `options`.
+// `scratch` holds each pass's output before it is copied back, so that a
+// caller that keeps both buffers around doesn't need to allocate once they
+// are big enough.
+fn apply_rules(dest: &mut String, scratch: &mut String, options: &Options) {
+    if apply_exceptions(dest, options.word_exceptions()) == Some(true) {
+        return;
+    }
+    let silent_letters = options.drops_silent_letters();
+    if let Some(key) = byte_rules::key(dest, silent_letters) {
+        dest.clear();
+        dest.push_str(key.as_str());
+        return;
+    }
+    apply_char_rules(dest, scratch, silent_letters, |_, _| {});
+}
+
+// Replaces `dest` with its exception, if it has one. Returns whether there
+// was one, and if so whether `dest` is now a key the rules should leave
+// alone.
+fn apply_exceptions(dest: &mut String, exceptions: &Exceptions) -> Option<bool> {
+    let (replacement, is_key) = match exceptions.lookup(dest)? {
+        exceptions::Exception::Spelling(spelling) => (spelling, false),
+        exceptions::Exception::Key(key) => (key, true),
+    };
+    dest.clear();
+    dest.push_str(replacement);
+    Some(is_key)
+}
+
+// The char version of the English rules, for words that `byte_rules::key`
+// can't handle. The silent letter rules only run if `silent_letters` is set.
+// `record` is called after each pass with its name and the word as it
+// stands, which is how `explain` builds its trace.
+fn apply_char_rules(
+    dest: &mut String,
+    scratch: &mut String,
+    silent_letters: bool,
+    mut record: impl FnMut(&'static str, &str),
+) {
+    apply_start_rules(dest);
+    record("start of word", dest);
+    if silent_letters {
+        let mut bytes = std::mem::take(dest).into_bytes();
+        let len = drop_silent_letters(&mut bytes);
+        bytes.truncate(len);
+        *dest = String::from_utf8(bytes).expect("only ASCII letters were removed");
+        record("silent letters", dest);
+    }
+    apply_end_rules(dest);
+    record("end of word", dest);
+    if dest.len() > 1 {
+        fold_doubles(dest, scratch);
+        record("double letters", dest);
+        fold_middle(dest, scratch);
+        record("middle of word", dest);
+        fold_consonants(dest, scratch);
+        record("final consonants", dest);
+    }
+}
+
+// The same as `normalize_word`, but always using the char version of the
+// rules, which the byte version is checked against.
+#[cfg(test)]
+fn normalize_word_by_chars(source: &str) -> String {
+    let mut dest = latinize(source).into_owned();
+    apply_char_rules(&mut dest, &mut String::new(), true, |_, _| {});
+    dest
+}
+
+// Lowercases `dest` and applies the start of word rules.
+fn apply_start_rules(dest: &mut String) {
     dest.make_ascii_lowercase();
 
     // **Start of word substitutions**
 
+    // British spellings of words that start with a soft c or a hard c
+    // s/^cae/se/;
+    if dest.starts_with("cae") {
+        dest.replace_range(0..3, "se");
+        return;
+    }
+    // s/^scep/skep/;
+    if dest.starts_with("scep") {
+        dest.replace_range(0..3, "ske");
+        return;
+    }
+
     let mut char_iter = dest.chars();
     let first_char: Option<char> = char_iter.next();
     let second_char: Option<char> = char_iter.next();
 
     match (first_char, second_char) {
+        // s/^[ao]e/e/;
+        (Some('a'), Some('e')) | (Some('o'), Some('e')) => dest.replace_range(0..2, "e"),
         // s/^c([^eh])/k$1/;
         (Some('c'), Some(v)) =>
         // turn a leading c into a k unless the second letter is e or h
@@ -45,9 +254,16 @@ pub fn normalize_word(source: &str) -> Cow<str> {
         (Some('j'), _) => dest.replace_range(0..1, "g"),
         (_, _) => {}
     }
+}
 
+// Applies the end of word rules to a lowercased `dest`.
+fn apply_end_rules(dest: &mut String) {
     // **End of word substitutions**
 
+    if let Some((n, replacement)) = american_ending(dest.as_bytes()) {
+        dest.replace_range((dest.len() - n).., replacement);
+    }
+
     let mut char_iter = dest.chars();
     let last_char: Option<char> = char_iter.next_back();
     let last_char2: Option<char> = char_iter.next_back();
@@ -122,212 +338,312 @@ pub fn normalize_word(source: &str) -> Cow<str> {
     }
     // Must happen after other changes
     // s/itly$/atly/;
-    replace_end_if(&mut dest, "itly", "atly");
+    replace_end_if(dest, "itly", "atly");
+}
 
-    // **Rest of word changes (everything but first char)**
+// Removes the silent letters from a lowercased word that has been through
+// the start of word rules, returning its new length. The rules only ever
+// remove ASCII letters or replace them with fewer, so they work in place on
+// the bytes and are shared by both versions of the rules.
+fn drop_silent_letters(word: &mut [u8]) -> usize {
+    let mut len = word.len();
+
+    // At the start of the word, if there's more to it than the cluster
+    match word[..len] {
+        // s/^rh(.)/r$1/;
+        [b'r', b'h', _, ..] => len = remove_byte(word, len, 1),
+        // s/^wr(.)/r$1/; s/^ps(.)/s$1/; s/^pn(.)/n$1/; s/^mn(.)/n$1/;
+        [b'w', b'r', _, ..] | [b'p', b's' | b'n', _, ..] | [b'm', b'n', _, ..] => {
+            len = remove_byte(word, len, 0)
+        }
+        _ => {}
+    }
 
-    if dest.len() > 1 {
-        let first_char: String = dest.chars().take(1).collect();
-
-        // Remove double letters. Don't skip first letter.
-        dest = dest
-            .chars()
-            .fold(String::with_capacity(dest.len()), |mut acc, c| {
-                // preserve ee and oo
-                if !acc.ends_with(c) || c == 'e' || c == 'o' {
-                    acc.push(c);
-                }
+    // In the middle of the word
+    let mut i = 1;
+    while i + 1 < len {
+        let silent = match word[i - 1..len] {
+            // s/([^o])wr/$1r/g; but not in dowry
+            [p, b'w', b'r', ..] => p != b'o',
+            // s/(e|ou)bt/$1t/g; s/btl/tl/g;
+            [b'e', b'b', b't', ..] | [_, b'b', b't', b'l', ..] => true,
+            [b'u', b'b', b't', ..] => i >= 2 && word[i - 2] == b'o',
+            _ => false,
+        };
+        if silent {
+            len = remove_byte(word, len, i);
+        }
+        i += 1;
+    }
+
+    // At the end of the word, or before an s, ed or ing
+    for suffix in [&b""[..], b"s", b"ed", b"ing", b"er"] {
+        let stem = match word[..len].strip_suffix(suffix) {
+            Some(stem) => stem.len(),
+            None => continue,
+        };
+        match word[..stem] {
+            // s/mb$/m/; but not before er, as in number
+            [_, .., b'm', b'b'] if suffix != b"er" => {
+                return remove_byte(word, len, stem - 1);
+            }
+            // s/alk$/ok/;
+            [_, .., b'a', b'l', b'k'] => {
+                word[stem - 3] = b'o';
+                return remove_byte(word, len, stem - 2);
+            }
+            // s/olk$/ok/;
+            [_, .., b'o', b'l', b'k'] => return remove_byte(word, len, stem - 2),
+            _ => {}
+        }
+    }
+    len
+}
 
-                // These next few just need to happen before the full pass below.
-                // We do a length check because we don't want to catch the first character
-                // in these tests, which should only apply to middle and end of word matches.
+// Removes the byte at `i` from `word[..len]` and returns the new length.
+fn remove_byte(word: &mut [u8], len: usize, i: usize) -> usize {
+    word.copy_within(i + 1..len, i);
+    len - 1
+}
+
+// Finds a British spelling at the end of a lowercased word, returning how
+// many bytes to replace and the American spelling to put in their place. This
+// runs before the other end of word rules, in both versions of them.
+fn american_ending(word: &[u8]) -> Option<(usize, &'static str)> {
+    match word {
+        // s/ogue$/og/; s/ogues$/ogs/; s/ogued$/oged/;
+        [.., b'o', b'g', b'u', b'e'] => Some((2, "")),
+        [.., b'o', b'g', b'u', b'e', b's'] => Some((3, "s")),
+        [.., b'o', b'g', b'u', b'e', b'd'] => Some((3, "ed")),
+        // s/([bgtv]|ch)re$/$1er/; s/([bgtv]|ch)res$/$1ers/;
+        [_, .., b'b' | b'g' | b't' | b'v', b'r', b'e'] | [.., b'c', b'h', b'r', b'e'] => {
+            Some((2, "er"))
+        }
+        [_, .., b'b' | b'g' | b't' | b'v', b'r', b'e', b's'] => Some((3, "ers")),
+        // s/([bgtv])red$/$1ered/;
+        [_, .., b'b' | b'g' | b't' | b'v', b'r', b'e', b'd'] => Some((3, "ered")),
+        // s/mmes$/ms/;
+        [.., b'm', b'm', b'e', b's'] => Some((3, "s")),
+        // s/dgement$/dgment/; s/dgements$/dgments/;
+        [.., b'd', b'g', b'e', b'm', b'e', b'n', b't'] => Some((5, "ment")),
+        [.., b'd', b'g', b'e', b'm', b'e', b'n', b't', b's'] => Some((6, "ments")),
+        // s/oea$/ea/;
+        [.., b'o', b'e', b'a'] => Some((3, "ea")),
+        // s/xe$/x/;
+        [.., b'x', b'e'] => Some((1, "")),
+        _ => None,
+    }
+}
+
+// The rest of word changes (everything but the first char) come in three
+// passes, each writing into `scratch` and copying the result back to `dest`.
+
+// Removes double letters and applies the rules that must run before the rest.
+fn fold_doubles(dest: &mut String, scratch: &mut String) {
+    // Remove double letters. Don't skip first letter.
+    scratch.clear();
+    for c in dest.chars() {
+        // preserve ee and oo
+        if !scratch.ends_with(c) || c == 'e' || c == 'o' {
+            scratch.push(c);
+        }
+    }
+    dest.clear();
+    dest.push_str(scratch);
+
+    // These next few just need to happen before the full pass below. Apart
+    // from plough and dough they don't apply to the first character, which
+    // should only be changed by the start of word rules.
+    let first_len = dest.chars().next().map_or(0, char::len_utf8);
+    let (word_rules, middle_rules) = early_rules();
+    word_rules.apply_from(dest, 0, scratch);
+    middle_rules.apply_from(dest, first_len, scratch);
+}
 
+// The rules `fold_doubles` runs once double letters are gone, as the ones
+// that can match anywhere in the word and the ones that can't match its first
+// character. Each phase runs over the output of the one before, in one pass
+// whatever the number of rules.
+pub(crate) fn early_rules() -> &'static (Rules, Rules) {
+    static RULES: OnceLock<(Rules, Rules)> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let word = Rules::new().phase(vec![
+            // s/plough/plow/g;
+            ("plough", "plow"),
+            // s/dough/do/g;
+            ("dough", "do"),
+        ]);
+        let middle = Rules::new()
+            .phase(vec![
                 // s/ought/ot/g;
-                if acc.len() > 5 && acc.ends_with("ought") {
-                    replace_last(&mut acc, 5, "ot");
-                }
-                // s/plough/plow/g;
-                if acc.len() > 5 && acc.ends_with("plough") {
-                    replace_last(&mut acc, 6, "plow");
-                }
-                // s/dough/do/g;
-                if acc.len() > 4 && acc.ends_with("dough") {
-                    replace_last(&mut acc, 5, "do");
-                }
+                ("ought", "ot"),
                 // s/ight/ite/g;
-                if acc.len() > 4 && acc.ends_with("ight") {
-                    // This must be done in an early pass
-                    replace_last(&mut acc, 4, "ite");
-                }
+                ("ight", "ite"),
                 // s/eagh/eg/g;
-                if acc.len() > 4 && acc.ends_with("eagh") {
-                    // This must be done in an early pass
-                    replace_last(&mut acc, 4, "eg");
-                }
+                ("eagh", "eg"),
                 // s/eaga/ega/g;
-                if acc.len() > 4 && acc.ends_with("eaga") {
-                    // This must be done in an early pass
-                    replace_last(&mut acc, 4, "ega");
-                }
+                ("eaga", "ega"),
                 // s/our/or/g;
-                if acc.len() > 3 && acc.ends_with("our") {
-                    // This must be done in an early pass
-                    replace_last(&mut acc, 3, "or");
-                }
-                // We already did this for end of word, but need mid-word
-                // and needs to be run early.
-                // s/rey/ray/g;
-                if acc.len() > 3 && acc.ends_with("rey") {
-                    replace_last(&mut acc, 2, "ay");
+                ("our", "or"),
+            ])
+            // We already did this for end of word, but need mid-word
+            // and needs to be run early.
+            // s/rey/ray/g;
+            .phase(vec![("rey", "ray")])
+            // s/[uae]r/r/g;
+            .phase(vec![("ur", "r"), ("ar", "r"), ("er", "r")]);
+        (word, middle)
+    })
+}
+
+// The rules for the middle of the word.
+fn fold_middle(dest: &mut String, scratch: &mut String) {
+    let first_char = dest.chars().next().unwrap_or(' ');
+    let first_len = first_char.len_utf8();
+
+    scratch.clear();
+    dest.chars()
+        .skip(1)
+        .fold((&mut *scratch, ' ', ' '), |(acc, c1, c2), c3| {
+            match (c1, c2, c3) {
+                // s/([^aeiou])al/$1l/g;
+                (consonant, 'a', 'l') => {
+                    if consonant == ' ' || is_vowel(&consonant, false) {
+                        acc.push('l');
+                    } else {
+                        replace_last(acc, 2, "");
+                        acc.push(consonant);
+                        acc.push('l');
+                    }
                 }
-                // s/[uae]r/r/g;
-                if acc.len() > 2
-                    && (acc.ends_with("ur") || acc.ends_with("ar") || acc.ends_with("er"))
-                {
-                    // This must be done in an early pass
-                    replace_last(&mut acc, 2, "r");
+                // s/igh/i/g;
+                ('i', 'g', 'h') => replace_last(acc, 1, ""),
+                // s/gh/f/g;
+                (_, 'g', 'h') => replace_last(acc, 1, "f"),
+                // s/eu/e/g;
+                (_, 'e', 'u') => {} // don't add the u
+                // s/ea/ee/g;
+                (_, 'e', 'a') => acc.push('e'),
+                // s/ei/ee/g;
+                (_, 'e', 'i') => acc.push('e'),
+                // s/ie/ee/g;
+                (_, 'i', 'e') => replace_last(acc, 1, "ee"),
+                // s/gue/gu/g;
+                ('g', 'u', 'e') => {} // don't add the e
+                // s/ue/e/g;
+                (_, 'u', 'e') => replace_last(acc, 1, "e"),
+                // s/au/ua/g;
+                (_, 'a', 'u') => replace_last(acc, 1, "ua"),
+                // s/ai/ae/g;
+                (_, 'a', 'i') => acc.push('e'),
+                // s/ae/e/g;
+                (_, 'a', 'e') => replace_last(acc, 1, "e"),
+                // s/gn/n/g;
+                (_, 'g', 'n') => replace_last(acc, 1, "n"),
+                // s/(mn|nm)/m/g;
+                (_, 'm', 'n') => {} // don't add the n
+                (_, 'n', 'm') => replace_last(acc, 1, "m"),
+                // s/sc/c/g;
+                (_, 's', 'c') => replace_last(acc, 1, "c"),
+                // s/ou/o/g;
+                (_, 'o', 'u') => {} // don't add the u
+                // s/uo/o/g;
+                (_, 'u', 'o') => replace_last(acc, 1, "o"),
+                // s/ate/ite/g;
+                ('a', 't', 'e') => replace_last(acc, 2, "ite"),
+                // s/ph/f/g;
+                (_, 'p', 'h') => replace_last(acc, 1, "f"),
+                // s/an/en/g;
+                (_, 'a', 'n') => replace_last(acc, 1, "en"),
+                // s/in/en/g;
+                (_, 'i', 'n') => replace_last(acc, 1, "en"),
+                // s/ao/oa/g;
+                (_, 'a', 'o') => replace_last(acc, 1, "oa"),
+                // s/y(.)/i$1/g; note: make sure this doesn't match at the end of the word
+                // only convert y to i in the middle
+                (_, 'y', v) => {
+                    replace_last(acc, 1, "i");
+                    acc.push(v);
                 }
-                acc
-            });
-
-        let (new_dest, _, _) = dest.chars().skip(1).fold(
-            (String::with_capacity(dest.len()), ' ', ' '),
-            |(mut acc, c1, c2), c3| {
-                match (c1, c2, c3) {
-                    // s/([^aeiou])al/$1l/g;
-                    (consonant, 'a', 'l') => {
-                        if consonant == ' ' || is_vowel(&consonant, false) {
-                            acc.push('l');
-                        } else {
-                            replace_last(&mut acc, 2, format!("{}l", consonant).as_str());
-                        }
-                    }
-                    // s/igh/i/g;
-                    ('i', 'g', 'h') => replace_last(&mut acc, 1, ""),
-                    // s/gh/f/g;
-                    (_, 'g', 'h') => replace_last(&mut acc, 1, "f"),
-                    // s/eu/e/g;
-                    (_, 'e', 'u') => {} // don't add the u
-                    // s/ea/ee/g;
-                    (_, 'e', 'a') => acc.push('e'),
-                    // s/ei/ee/g;
-                    (_, 'e', 'i') => acc.push('e'),
-                    // s/ie/ee/g;
-                    (_, 'i', 'e') => replace_last(&mut acc, 1, "ee"),
-                    // s/gue/gu/g;
-                    ('g', 'u', 'e') => {} // don't add the e
-                    // s/ue/e/g;
-                    (_, 'u', 'e') => replace_last(&mut acc, 1, "e"),
-                    // s/au/ua/g;
-                    (_, 'a', 'u') => replace_last(&mut acc, 1, "ua"),
-                    // s/ai/ae/g;
-                    (_, 'a', 'i') => acc.push('e'),
-                    // s/ae/e/g;
-                    (_, 'a', 'e') => replace_last(&mut acc, 1, "e"),
-                    // s/gn/n/g;
-                    (_, 'g', 'n') => replace_last(&mut acc, 1, "n"),
-                    // s/(mn|nm)/m/g;
-                    (_, 'm', 'n') => {} // don't add the n
-                    (_, 'n', 'm') => replace_last(&mut acc, 1, "m"),
-                    // s/sc/c/g;
-                    (_, 's', 'c') => replace_last(&mut acc, 1, "c"),
-                    // s/ou/o/g;
-                    (_, 'o', 'u') => {} // don't add the u
-                    // s/uo/o/g;
-                    (_, 'u', 'o') => replace_last(&mut acc, 1, "o"),
-                    // s/ate/ite/g;
-                    ('a', 't', 'e') => replace_last(&mut acc, 2, "ite"),
-                    // s/ph/f/g;
-                    (_, 'p', 'h') => replace_last(&mut acc, 1, "f"),
-                    // s/an/en/g;
-                    (_, 'a', 'n') => replace_last(&mut acc, 1, "en"),
-                    // s/in/en/g;
-                    (_, 'i', 'n') => replace_last(&mut acc, 1, "en"),
-                    // s/ao/oa/g;
-                    (_, 'a', 'o') => replace_last(&mut acc, 1, "oa"),
-                    // s/y(.)/i$1/g; note: make sure this doesn't match at the end of the word
-                    // only convert y to i in the middle
-                    (_, 'y', v) => replace_last(&mut acc, 1, format!("i{}", v).as_str()),
-                    // s/anc/enc/g;
-                    ('a', 'n', 'c') => replace_last(&mut acc, 2, "enc"),
-                    // s/gm/m/g;
-                    (_, 'g', 'm') => replace_last(&mut acc, 1, "m"),
-                    // s/cq/k/g;
-                    (_, 'c', 'q') => replace_last(&mut acc, 1, "k"),
-                    // s/ck/k/g;
-                    (_, 'c', 'k') => replace_last(&mut acc, 1, "k"),
-                    // s/qu/k/g;
-                    (_, 'q', 'u') => replace_last(&mut acc, 1, "k"),
-                    // s/ce/se/g;
-                    (_, 'c', 'e') => replace_last(&mut acc, 1, "se"),
-                    // s/t[sc]h/sh/g;
-                    ('t', 's', 'h') | ('t', 'c', 'h') => replace_last(&mut acc, 2, "sh"),
-                    // s/ch/sh/g;
-                    (_, 'c', 'h') => replace_last(&mut acc, 1, "sh"),
-                    // s/dg/g/g;
-                    (_, 'd', 'g') => replace_last(&mut acc, 1, "g"),
-                    // s/ore/or/g;
-                    ('o', 'r', 'e') => {} // don't add the e
-                    // s/([^sth]+)h/$1/g;
-                    // get rid of all h's except for start and ch/sh/th
-                    (_, p, 'h') => {
-                        // keep the h if the preceding char is c or s or t
-                        if p == 'c'
-                            || p == 's'
-                            || p == 't'
-                            || (p == ' '
-                                && (first_char == "c" || first_char == "t" || first_char == "s"))
-                        {
-                            acc.push('h');
-                        }
+                // s/anc/enc/g;
+                ('a', 'n', 'c') => replace_last(acc, 2, "enc"),
+                // s/gm/m/g;
+                (_, 'g', 'm') => replace_last(acc, 1, "m"),
+                // s/cq/k/g;
+                (_, 'c', 'q') => replace_last(acc, 1, "k"),
+                // s/ck/k/g;
+                (_, 'c', 'k') => replace_last(acc, 1, "k"),
+                // s/qu/k/g;
+                (_, 'q', 'u') => replace_last(acc, 1, "k"),
+                // s/ce/se/g;
+                (_, 'c', 'e') => replace_last(acc, 1, "se"),
+                // s/t[sc]h/sh/g;
+                ('t', 's', 'h') | ('t', 'c', 'h') => replace_last(acc, 2, "sh"),
+                // s/ch/sh/g;
+                (_, 'c', 'h') => replace_last(acc, 1, "sh"),
+                // s/dg/g/g;
+                (_, 'd', 'g') => replace_last(acc, 1, "g"),
+                // s/ore/or/g;
+                ('o', 'r', 'e') => {} // don't add the e
+                // s/([^sth]+)h/$1/g;
+                // get rid of all h's except for start and ch/sh/th
+                (_, p, 'h') => {
+                    // keep the h if the preceding char is c or s or t
+                    if p == 'c'
+                        || p == 's'
+                        || p == 't'
+                        || (p == ' '
+                            && (first_char == 'c' || first_char == 't' || first_char == 's'))
+                    {
+                        acc.push('h');
                     }
+                }
 
-                    _ => acc.push(c3),
-                };
+                _ => acc.push(c3),
+            };
 
-                (acc, c2, c3)
-            },
-        );
-        dest = first_char.clone() + &new_dest;
-
-        dest = first_char
-            + &dest.char_indices().skip(1).fold(
-                String::with_capacity(dest.len()),
-                |mut acc, (byte_idx, c)| {
-                    match c {
-                        // s/q/k/g;
-                        'q' => acc.push('k'),
-                        // s/x/k/g;
-                        'x' => acc.push('k'),
-                        // s/z/s/g;
-                        'z' => acc.push('s'),
-
-                        // s/b/p/g;
-                        'b' => {
-                            // Only do this ones if we aren't on the last char
-                            if byte_idx < dest.len() {
-                                acc.push('p');
-                            }
-                        }
-                        // s/d/t/g;
-                        'd' => {
-                            if byte_idx < dest.len() {
-                                acc.push('t');
-                            }
-                        }
-
-                        _ => acc.push(c),
+            (acc, c2, c3)
+        });
+    dest.truncate(first_len);
+    dest.push_str(scratch);
+}
+
+// Folds the consonants that sound alike.
+fn fold_consonants(dest: &mut String, scratch: &mut String) {
+    let first_len = dest.chars().next().map_or(0, char::len_utf8);
+
+    scratch.clear();
+    dest.char_indices()
+        .skip(1)
+        .fold(&mut *scratch, |acc, (byte_idx, c)| {
+            match c {
+                // s/q/k/g;
+                'q' => acc.push('k'),
+                // s/x/k/g;
+                'x' => acc.push('k'),
+                // s/z/s/g;
+                'z' => acc.push('s'),
+
+                // s/b/p/g;
+                'b' => {
+                    // Only do this ones if we aren't on the last char
+                    if byte_idx < dest.len() {
+                        acc.push('p');
                     }
-                    acc
-                },
-            );
-    }
+                }
+                // s/d/t/g;
+                'd' => {
+                    if byte_idx < dest.len() {
+                        acc.push('t');
+                    }
+                }
 
-    if source == dest {
-        Cow::Borrowed(source)
-    } else {
-        Cow::Owned(dest)
-    }
+                _ => acc.push(c),
+            }
+            acc
+        });
+    dest.truncate(first_len);
+    dest.push_str(scratch);
 }
 
 fn replace_last(s: &mut String, n: usize, replacement: &str) {
@@ -433,6 +749,8 @@ mod tests {
         assert_eq!(normalize_word("cent"), normalize_word("sent"));
         assert_eq!(normalize_word("cite"), normalize_word("site"));
         assert_eq!(normalize_word("gray"), normalize_word("grey"));
+        assert_eq!(normalize_word("nighttime"), normalize_word("nightime"));
+        assert_eq!(normalize_word("sourdough"), normalize_word("sordo"));
     }
 
     #[test]
@@ -451,7 +769,29 @@ mod tests {
             normalize_word("cancellation"),
             normalize_word("cancelation")
         );
-        // assert_eq!(normalize_word("catalogue"), normalize_word("catalog"));
+        assert_eq!(normalize_word("catalogue"), normalize_word("catalog"));
+        assert_eq!(normalize_word("centre"), normalize_word("center"));
+        assert_eq!(normalize_word("theatre"), normalize_word("theater"));
+        assert_eq!(normalize_word("defence"), normalize_word("defense"));
+        assert_eq!(normalize_word("licence"), normalize_word("license"));
+        assert_eq!(normalize_word("programme"), normalize_word("program"));
+        assert_eq!(normalize_word("dialogue"), normalize_word("dialog"));
+        assert_eq!(normalize_word("paediatric"), normalize_word("pediatric"));
+    }
+
+    #[test]
+    fn bundled_british_spellings() {
+        let pairs = read_pairs(include_str!("../data/uk_us.csv").as_bytes()).unwrap();
+        // the hand-picked sample described at the top of the file, not the
+        // full list of about 1,700 pairs it was taken from
+        assert_eq!(pairs.len(), 190);
+        assert!(pairs.iter().all(|(_, _, should_match)| *should_match));
+        let failures: Vec<String> = pairs
+            .iter()
+            .filter(|(british, american, _)| normalize_word(british) != normalize_word(american))
+            .map(|(british, american, _)| format!("{}/{}", british, american))
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join(", "));
     }
 
     #[test]
@@ -481,6 +821,159 @@ mod tests {
         assert_eq!(normalize_word("do"), "do");
     }
 
+    #[test]
+    fn greek_matches() {
+        let options = Options::new().script(Script::Greek);
+        let greek = |w| normalize_word_with(w, &options).into_owned();
+        assert_eq!(greek("Παπαδόπουλος"), normalize_word("Papadopoulos"));
+        assert_eq!(greek("Γιώργος"), normalize_word("Giorgos"));
+        assert_eq!(greek("Θεόδωρος"), normalize_word("Theodoros"));
+        assert_eq!(greek("Φίλιππος"), normalize_word("Philippos"));
+        assert_eq!(greek("Χρήστος"), normalize_word("Christos"));
+        assert_eq!(greek("Ευάγγελος"), normalize_word("Evangelos"));
+        // Latin input is unaffected by the Greek stage
+        assert_eq!(greek("Papadopoulos"), normalize_word("Papadopoulos"));
+        // without the stage Greek passes straight through
+        assert_ne!(
+            normalize_word("Παπαδόπουλος"),
+            normalize_word("Papadopoulos")
+        );
+    }
+
+    #[test]
+    fn unchanged_words_are_borrowed() {
+        assert!(matches!(normalize_word("smith"), Cow::Borrowed("smith")));
+        assert!(matches!(normalize_word("Smith"), Cow::Owned(_)));
+        assert!(matches!(normalize_word("mark"), Cow::Owned(_)));
+        let options = Options::new().script(Script::Greek);
+        assert!(matches!(
+            normalize_word_with("smith", &options),
+            Cow::Borrowed("smith")
+        ));
+    }
+
+    #[test]
+    fn decomposed_input() {
+        assert_eq!(normalize_word("Jose\u{0301}"), normalize_word("José"));
+        assert_eq!(normalize_word("Jose\u{0301}"), normalize_word("Jose"));
+        assert_eq!(
+            normalize_word("\u{1D40D}oe\u{0308}l"),
+            normalize_word("Noel")
+        );
+    }
+
+    #[test]
+    fn locale_matches() {
+        let german = Options::new().locale(Locale::German);
+        let german = |w| normalize_word_with(w, &german).into_owned();
+        assert_eq!(german("Müller"), german("Mueller"));
+        assert_eq!(german("SCHRÖDER"), german("Schroeder"));
+
+        let turkish = Options::new().locale(Locale::Turkish);
+        let turkish = |w| normalize_word_with(w, &turkish).into_owned();
+        assert_eq!(turkish("IŞIK"), turkish("Isik"));
+        assert_eq!(turkish("İLHAN"), turkish("Ilhan"));
+
+        let danish = Options::new().locale(Locale::Danish);
+        assert_eq!(
+            normalize_word_with("Århus", &danish),
+            normalize_word_with("Aarhus", &danish)
+        );
+
+        let vietnamese = Options::new().locale(Locale::Vietnamese);
+        assert_eq!(
+            normalize_word_with("Nguyễn", &vietnamese),
+            normalize_word("Nguyen")
+        );
+    }
+
+    #[test]
+    fn user_rules() {
+        // Dutch ij is usually written y in English spellings of names
+        let dutch = Options::new().rules(Rules::new().phase(vec![("ij", "y")]));
+        let dutch = |w| normalize_word_with(w, &dutch).into_owned();
+        assert_eq!(dutch("Dijkstra"), normalize_word("Dykstra"));
+        assert_eq!(dutch("VRIJ"), normalize_word("vry"));
+        assert_eq!(dutch("Smith"), normalize_word("Smith"));
+        assert_ne!(normalize_word("Dijkstra"), normalize_word("Dykstra"));
+
+        // the rules see the latinized word and run before the English rules
+        let rules = Rules::new()
+            .phase(vec![("muller", "miller")])
+            .phase(vec![("mill", "mil")]);
+        let options = Options::new().rules(rules);
+        assert_eq!(
+            normalize_word_with("Müller", &options),
+            normalize_word("Miler")
+        );
+    }
+
+    #[test]
+    fn exceptions() {
+        assert_eq!(normalize_word("colonel"), normalize_word("kernel"));
+        assert_eq!(normalize_word("Worcester"), normalize_word("Wooster"));
+        assert_eq!(normalize_word("LEICESTER"), normalize_word("Lester"));
+        assert_eq!(normalize_word("Siobhán"), normalize_word("Shivaun"));
+        let mut key = String::new();
+        normalize_into("Colonel", &mut key);
+        assert_eq!(key, normalize_word("kernel"));
+
+        // without the built-in list the rules get them wrong
+        let rules_only = Options::new().exceptions(Exceptions::new().builtin(false));
+        assert!(!words_match("colonel", "kernel", &rules_only));
+
+        // exceptions see the word after the other stages
+        let options = Options::new()
+            .rules(Rules::new().phase(vec![("ij", "y")]))
+            .exceptions(Exceptions::new().key("dykstra", "DIJKSTRA"));
+        assert_eq!(normalize_word_with("Dijkstra", &options), "DIJKSTRA");
+        assert_eq!(normalize_word_with("Dykstra", &options), "DIJKSTRA");
+        assert_eq!(
+            normalize_word_with("Colonel", &options),
+            normalize_word("kernel")
+        );
+    }
+
+    #[test]
+    fn silent_letters() {
+        // at the start of the word
+        assert_eq!(normalize_word("write"), normalize_word("rite"));
+        assert_eq!(normalize_word("Wright"), normalize_word("Right"));
+        assert_eq!(normalize_word("rhyme"), normalize_word("ryme"));
+        assert_eq!(normalize_word("psychology"), normalize_word("sychology"));
+        assert_eq!(normalize_word("pneumonia"), normalize_word("neumonia"));
+        assert_eq!(normalize_word("mnemonic"), normalize_word("nemonic"));
+        // in the middle
+        assert_eq!(normalize_word("rewrite"), normalize_word("rerite"));
+        assert_eq!(normalize_word("debtor"), normalize_word("detor"));
+        assert_eq!(normalize_word("doubtful"), normalize_word("doutful"));
+        assert_eq!(normalize_word("subtle"), normalize_word("suttle"));
+        assert_ne!(normalize_word("dowry"), normalize_word("dory"));
+        assert_ne!(normalize_word("subtract"), normalize_word("sutract"));
+        // at the end, and before s, ed and ing
+        assert_eq!(normalize_word("lamb"), normalize_word("lam"));
+        assert_eq!(normalize_word("lambs"), normalize_word("lams"));
+        assert_eq!(normalize_word("climbing"), normalize_word("climing"));
+        assert_eq!(normalize_word("autumn"), normalize_word("autum"));
+        assert_eq!(normalize_word("walk"), normalize_word("wok"));
+        assert_eq!(normalize_word("walked"), normalize_word("woked"));
+        assert_eq!(normalize_word("talker"), normalize_word("toker"));
+        assert_eq!(normalize_word("folk"), normalize_word("fok"));
+        assert_eq!(normalize_word("debt"), normalize_word("det"));
+        assert_eq!(normalize_word("doubt"), normalize_word("dout"));
+        assert_ne!(normalize_word("lamb"), normalize_word("lamp"));
+        assert_ne!(normalize_word("number"), normalize_word("numer"));
+        assert_ne!(normalize_word("mb"), normalize_word("m"));
+        assert_ne!(normalize_word("ps"), normalize_word("s"));
+
+        // the whole group can be turned off
+        let options = Options::new().silent_letters(false);
+        assert!(words_match("lamb", "lamp", &options));
+        assert!(!words_match("write", "rite", &options));
+        assert!(!words_match("walk", "wok", &options));
+        assert!(!words_match("debt", "det", &options));
+    }
+
     #[test]
     fn replace_end_if_tests() {
         let mut s = "word".to_string();
@@ -1,13 +1,629 @@
-use phonetic_normalizer::normalize_word;
-use std::io::{self, Read};
-
-fn main() -> io::Result<()> {
-  let mut buffer = String::new();
-  let mut stdin = io::stdin(); // We get `Stdin` here.
-  stdin.read_to_string(&mut buffer)?;
-  for word in buffer.split_whitespace() {
-    let normalized = normalize_word(&word);
-    println!("{}\t{}", word, normalized);
-  }
-  Ok(())
+mod config;
+mod csv;
+mod output;
+
+use config::Config;
+use output::{Fields, Format, RecordWriter};
+use phonetic_normalizer::{
+    collisions, diff, diff_keys, evaluate_with, explain, group_by_key, normalize_batch_with,
+    normalize_word_with, read_pairs, words_match, Evaluation, KeyDiff, Options,
+};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: phonetic-normalizer [normalize] [--format FORMAT] [--fields FIELDS] [INPUT [OUTPUT]]
+       phonetic-normalizer [normalize] --csv --column NAME... [INPUT [OUTPUT]]
+       phonetic-normalizer cluster [--format FORMAT] [INPUT [OUTPUT]]
+       phonetic-normalizer collisions [--lemmas FILE] [INPUT [OUTPUT]]
+       phonetic-normalizer compare WORD1 WORD2
+       phonetic-normalizer explain WORD...
+       phonetic-normalizer eval [--config SPEC]... [PAIRS]
+       phonetic-normalizer diff --config OLD --config NEW [--mapping FILE] [INPUT [OUTPUT]]
+       phonetic-normalizer diff --keys [--config NEW] [--mapping FILE] [KEYS [OUTPUT]]
+
+Commands:
+  normalize  Print each word of INPUT next to its normalized form, one per
+             line. This is the default.
+  cluster    Group the words of INPUT by key, one group per line with its
+             key, how many words it has counting repeats, and its distinct
+             words. The groups with the most distinct words come first.
+  collisions List every key shared by words of different lemmas, then how
+             many of the words share their key that way. FILE has a word
+             and its lemma on each line; other words are their own lemma.
+  compare    Print the keys of two words and whether they match. Exits with
+             0 if they do and 1 if they don't.
+  explain    Show each stage the words go through on the way to their keys.
+  eval       Read labeled pairs, one `word1,word2,should_match` per line,
+             and report the accuracy, precision, recall and F1 of the keys
+             and the pairs they get wrong. `should_match` is true or false,
+             yes or no, or 1 or 0. With more than one --config, they are
+             compared side by side. data/pairs.csv has a set of pairs, and
+             data/uk_us.csv has a sample of British and American
+             spellings.
+  diff       Normalize the words of INPUT with two configurations and list
+             every word whose key changed, the new keys that merge old
+             ones and the old keys that split, then how many pairs of
+             words gained or lost a match. With --keys, KEYS is the output
+             of normalize from an older release, and its keys are compared
+             with this release's.
+
+Options for all commands:
+  --config SPEC    The settings for the normalizer, separated by commas:
+                     script=NAME  transliterate greek, arabic, hebrew, han,
+                                  kana, hangul or devanagari first
+                     names=NAME   fold arabic, chinese, japanese, korean or
+                                  indic names
+                     locale=NAME  latinize as turkish, german, danish,
+                                  norwegian or vietnamese
+                     rules=FILE   apply user rules, a pattern and its
+                                  replacement per line, with blank lines
+                                  between phases
+                     exceptions=FILE
+                                  add exceptions, a word and the spelling
+                                  to normalize it as per line, or `=` and
+                                  the key to give it
+                     silent-letters=off
+                                  keep silent letters like the w in
+                                  write and the b in lamb
+                     default      none of them
+                   For eval only, `dm` matches words with a Daitch-Mokotoff
+                   code in common. eval takes it more than once, and diff
+                   takes the old and the new configuration.
+
+Options for normalize:
+  --format FORMAT  plain (the default), jsonl, csv or tsv. The plain format
+                   is tab separated with no escaping; CSV and TSV start with
+                   a header. Also for cluster.
+  --fields FIELDS  Extra fields for each word, separated by commas:
+                     version     the algorithm version of the key
+                     latinized   the word as the English rules first saw it
+                     dm          the Daitch-Mokotoff codes of the word
+                     trace       the word after each stage, as with explain
+  --csv            Read INPUT as CSV with a header, and write it back with a
+                   `NAME_phonetic` column added for each --column NAME. The
+                   words in each cell are normalized separately.
+  --column NAME    A column to normalize with --csv. Can be repeated.
+
+Options for diff:
+  --keys           Read a word and its old key on each line of KEYS,
+                   separated by a tab, as normalize writes them.
+  --mapping FILE   Write each old key that changed and the new key it
+                   became to FILE, separated by a tab. An old key on more
+                   than one line was split, so its words need normalizing
+                   again.
+
+Files default to stdin and stdout; `-` also means either.";
+
+// Stop reading ahead once a chunk has this many words, to bound memory on
+// input with no pauses in it.
+const CHUNK_WORDS: usize = 8192;
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let format: Format = take_flag(&mut args, "--format")
+        .map_or(Ok(Format::Plain), |f| f.parse())
+        .unwrap_or_else(|e| fail(&e));
+    let fields: Fields = take_flag(&mut args, "--fields")
+        .map_or(Ok(Fields::default()), |f| f.parse())
+        .unwrap_or_else(|e| fail(&e));
+    let csv = take_switch(&mut args, "--csv");
+    let lemmas = take_flag(&mut args, "--lemmas");
+    let keys = take_switch(&mut args, "--keys");
+    let mapping = take_flag(&mut args, "--mapping");
+    let configs = std::iter::from_fn(|| take_flag(&mut args, "--config"))
+        .map(|spec| Config::parse(&spec).unwrap_or_else(|e| fail(&e)))
+        .collect::<Vec<Config>>();
+    let columns: Vec<String> = std::iter::from_fn(|| take_flag(&mut args, "--column")).collect();
+    if let Some(flag) = args.iter().find(|arg| arg.starts_with("--")) {
+        fail(&format!("unknown option `{}`", flag));
+    }
+    let mode = match (csv, columns.is_empty()) {
+        (false, true) => Mode::Records(format, fields),
+        (true, false) if format == Format::Plain && fields == Fields::default() => {
+            Mode::CsvColumns(columns)
+        }
+        (true, false) => fail("--csv can't be used with --format or --fields"),
+        (true, true) => fail("--csv needs at least one --column"),
+        (false, false) => fail("--column needs --csv"),
+    };
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => ("normalize", &args[..]),
+    };
+    if command == "diff" && !keys && configs.len() != 2 {
+        fail("diff needs the old and the new --config, or --keys");
+    }
+    let configs = if configs.is_empty() {
+        vec![Config::default()]
+    } else {
+        configs
+    };
+    let several_configs = command == "eval" || (command == "diff" && !keys);
+    if configs.len() > 1 && !several_configs {
+        fail("only eval and diff take more than one --config");
+    }
+    let options = match command {
+        "eval" => Options::default(),
+        _ => configs[0].options().unwrap_or_else(|e| fail(&e)).clone(),
+    };
+    if lemmas.is_some() && command != "collisions" {
+        fail("--lemmas is only for collisions");
+    }
+    if (keys || mapping.is_some()) && command != "diff" {
+        fail("--keys and --mapping are only for diff");
+    }
+    if command == "diff"
+        && !matches!(mode, Mode::Records(Format::Plain, fields) if fields == Fields::default())
+    {
+        fail("diff only takes --config, --keys and --mapping");
+    }
+    let lemmas = lemmas.as_deref();
+    let diff_with = match (command, keys) {
+        ("diff", true) => Some(DiffWith::Keys(options.clone())),
+        ("diff", false) => {
+            let new = configs[1].options().unwrap_or_else(|e| fail(&e));
+            Some(DiffWith::Configs(options.clone(), new.clone()))
+        }
+        _ => None,
+    };
+    let diff_with = diff_with.as_ref();
+    let mapping = mapping.as_deref();
+    let result = match (command, rest) {
+        ("normalize", []) => run_normalize("-", "-", &mode, &options),
+        ("normalize", [input]) => run_normalize(input, "-", &mode, &options),
+        ("normalize", [input, output]) => run_normalize(input, output, &mode, &options),
+        ("cluster", []) => run_cluster("-", "-", &mode, &options),
+        ("cluster", [input]) => run_cluster(input, "-", &mode, &options),
+        ("cluster", [input, output]) => run_cluster(input, output, &mode, &options),
+        ("collisions", []) => run_collisions("-", "-", lemmas, &mode, &options),
+        ("collisions", [input]) => run_collisions(input, "-", lemmas, &mode, &options),
+        ("collisions", [input, output]) => run_collisions(input, output, lemmas, &mode, &options),
+        ("compare", [a, b]) => run_compare(a, b, &options),
+        ("explain", words) if !words.is_empty() => run_explain(words, &options),
+        ("eval", []) => run_eval("-", &configs),
+        ("eval", [pairs]) => run_eval(pairs, &configs),
+        ("diff", []) => run_diff("-", "-", diff_with.unwrap(), mapping),
+        ("diff", [input]) => run_diff(input, "-", diff_with.unwrap(), mapping),
+        ("diff", [input, output]) => run_diff(input, output, diff_with.unwrap(), mapping),
+        ("normalize" | "cluster" | "collisions" | "compare" | "explain" | "eval" | "diff", _) => {
+            usage_error()
+        }
+        // without a command, the arguments are the files to normalize
+        _ => match &args[..] {
+            [input] => run_normalize(input, "-", &mode, &options),
+            [input, output] => run_normalize(input, output, &mode, &options),
+            _ => usage_error(),
+        },
+    };
+
+    match result {
+        Ok(()) => {}
+        // the reader went away, e.g. `| head`
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("phonetic-normalizer: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+// What the normalize command writes.
+enum Mode {
+    // a record for every word
+    Records(Format, Fields),
+    // the input CSV with key columns added
+    CsvColumns(Vec<String>),
+}
+
+// Where the diff command gets its old and new keys.
+enum DiffWith {
+    // the old and new options for a list of words
+    Configs(Options, Options),
+    // the old keys from a file, with the options for the new ones
+    Keys(Options),
+}
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("phonetic-normalizer: {}", message);
+    process::exit(2);
+}
+
+// Removes `--name VALUE` or `--name=VALUE` from `args`, returning the value.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args
+        .iter()
+        .position(|arg| arg == name || arg.starts_with(&format!("{}=", name)))?;
+    let flag = args.remove(i);
+    match flag.split_once('=') {
+        Some((_, value)) => Some(value.to_string()),
+        None if i < args.len() => Some(args.remove(i)),
+        None => fail(&format!("{} needs a value", name)),
+    }
+}
+
+// Removes `name` from `args`, returning whether it was there.
+fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    let found = args.iter().position(|arg| arg == name);
+    if let Some(i) = found {
+        args.remove(i);
+    }
+    found.is_some()
+}
+
+fn run_normalize(input: &str, output: &str, mode: &Mode, options: &Options) -> io::Result<()> {
+    let writer = writer(output)?;
+    match mode {
+        Mode::Records(format, fields) => {
+            let records = RecordWriter::new(writer, *format, *fields);
+            normalize_stream(reader(input)?, records, options)
+        }
+        Mode::CsvColumns(columns) => {
+            csv::add_key_columns(csv::Reader::new(reader(input)?), writer, columns, options)
+        }
+    }
+}
+
+fn run_cluster(input: &str, output: &str, mode: &Mode, options: &Options) -> io::Result<()> {
+    let format = match mode {
+        Mode::Records(format, fields) if *fields == Fields::default() => *format,
+        _ => fail("cluster only takes --format"),
+    };
+    let mut error = None;
+    let groups = group_by_key(read_words(reader(input)?, &mut error), options);
+    if let Some(e) = error {
+        return Err(e);
+    }
+    output::write_groups(writer(output)?, format, &groups)
+}
+
+fn run_collisions(
+    input: &str,
+    output: &str,
+    lemmas: Option<&str>,
+    mode: &Mode,
+    options: &Options,
+) -> io::Result<()> {
+    if !matches!(mode, Mode::Records(Format::Plain, fields) if *fields == Fields::default()) {
+        fail("collisions only takes --lemmas");
+    }
+    let lemmas = match lemmas {
+        Some(path) => Some(read_lemmas(path)?),
+        None => None,
+    };
+    let mut error = None;
+    let words = read_words(reader(input)?, &mut error);
+    let report = collisions(words, lemmas.as_ref(), options);
+    if let Some(e) = error {
+        return Err(e);
+    }
+
+    let mut writer = writer(output)?;
+    for collision in report.collisions() {
+        write!(writer, "{}", collision.key())?;
+        for (lemma, words) in collision.lemmas() {
+            write!(writer, "\t{}: {}", lemma, words.join(" "))?;
+        }
+        writeln!(writer)?;
+    }
+    writeln!(
+        writer,
+        "{} of {} words ({:.2}%) share a key with another lemma; {} of {} keys collide, \
+         matching {} pairs of lemmas",
+        report.colliding_words(),
+        report.words(),
+        report.false_positive_rate() * 100.0,
+        report.collisions().len(),
+        report.keys(),
+        report.false_matches()
+    )?;
+    writer.flush()
+}
+
+// Reads a map of words to lemmas, a word and its lemma on each line,
+// separated by a tab, comma or spaces.
+fn read_lemmas(path: &str) -> io::Result<HashMap<String, String>> {
+    let mut lemmas = HashMap::new();
+    let mut line = String::new();
+    let mut reader = reader(path)?;
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(lemmas);
+        }
+        number += 1;
+        let fields: Vec<&str> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        match fields[..] {
+            [] => {}
+            [word, lemma] => {
+                lemmas.insert(word.to_lowercase(), lemma.to_string());
+            }
+            _ => return Err(bad_line(path, number, "expected a word and its lemma")),
+        }
+    }
+}
+
+fn run_compare(a: &str, b: &str, options: &Options) -> io::Result<()> {
+    println!("{}\t{}", a, normalize_word_with(a, options));
+    println!("{}\t{}", b, normalize_word_with(b, options));
+    if words_match(a, b, options) {
+        println!("match");
+        Ok(())
+    } else {
+        println!("no match");
+        process::exit(1);
+    }
+}
+
+fn run_explain(words: &[String], options: &Options) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            writeln!(stdout)?;
+        }
+        write!(stdout, "{}", explain(word, options))?;
+    }
+    Ok(())
+}
+
+fn run_eval(path: &str, configs: &[Config]) -> io::Result<()> {
+    let pairs = read_pairs(reader(path)?).map_err(|e| with_path(e, path))?;
+    let evaluations: Vec<Evaluation> = configs
+        .iter()
+        .map(|config| evaluate_with(&pairs, config.matcher()))
+        .collect();
+
+    let width = configs
+        .iter()
+        .map(|config| config.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    let mut stdout = io::stdout().lock();
+    writeln!(
+        stdout,
+        "{:width$}  {:>6}  {:>8}  {:>9}  {:>6}  {:>6}",
+        "config",
+        "pairs",
+        "accuracy",
+        "precision",
+        "recall",
+        "f1",
+        width = width
+    )?;
+    for (config, evaluation) in configs.iter().zip(&evaluations) {
+        writeln!(
+            stdout,
+            "{:width$}  {:>6}  {:>7.1}%  {:>8.1}%  {:>5.1}%  {:>5.1}%",
+            config.name,
+            evaluation.pairs(),
+            evaluation.accuracy() * 100.0,
+            evaluation.precision() * 100.0,
+            evaluation.recall() * 100.0,
+            evaluation.f1() * 100.0,
+            width = width
+        )?;
+    }
+    for (config, evaluation) in configs.iter().zip(&evaluations) {
+        if evaluation.failures().is_empty() {
+            continue;
+        }
+        writeln!(stdout, "\nfailures with {}:", config.name)?;
+        for (word1, word2, should_match) in evaluation.failures() {
+            let expected = if *should_match {
+                "should match"
+            } else {
+                "should not match"
+            };
+            writeln!(stdout, "  {},{}\t{}", word1, word2, expected)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_diff(input: &str, output: &str, with: &DiffWith, mapping: Option<&str>) -> io::Result<()> {
+    let diff = match with {
+        DiffWith::Configs(old, new) => {
+            // only the first of each word counts, so only those are kept
+            let mut error = None;
+            let mut seen = HashSet::new();
+            let words: Vec<String> = read_words(reader(input)?, &mut error)
+                .filter(|word| seen.insert(word.clone()))
+                .collect();
+            if let Some(e) = error {
+                return Err(e);
+            }
+            diff(&words, old, new)
+        }
+        DiffWith::Keys(options) => {
+            let entries = read_keys(input, reader(input)?)?;
+            let words: Vec<&str> = entries.iter().map(|(word, _)| word.as_str()).collect();
+            let new_keys = normalize_batch_with(&words, options);
+            diff_keys(
+                entries
+                    .iter()
+                    .zip(new_keys)
+                    .map(|((word, old), new)| (word, old.clone(), new)),
+            )
+        }
+    };
+    if let Some(path) = mapping {
+        let mut writer = writer(path)?;
+        for (old, new) in diff.mapping() {
+            writeln!(writer, "{}\t{}", old, new)?;
+        }
+        writer.flush()?;
+    }
+    write_diff(writer(output)?, &diff)
+}
+
+// Reads the output of normalize: a word and its key on each line, separated
+// by a tab, with any other fields after them. The header of the TSV format is
+// skipped, and so is every line after the first for the same word.
+fn read_keys<R: BufRead>(path: &str, mut reader: R) -> io::Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    let mut line = Vec::new();
+    for number in 1.. {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&line);
+        let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+        match fields[..] {
+            [""] => {}
+            ["word", "key", ..] if number == 1 => {}
+            [word, key, ..] => {
+                if seen.insert(word.to_string()) {
+                    entries.push((word.to_string(), key.to_string()));
+                }
+            }
+            _ => return Err(bad_line(path, number, "expected a word and its key")),
+        }
+    }
+    Ok(entries)
+}
+
+fn write_diff<W: Write>(mut writer: W, diff: &KeyDiff) -> io::Result<()> {
+    for (word, old, new) in diff.changed() {
+        writeln!(writer, "{}\t{} -> {}", word, old, new)?;
+    }
+    for (new, old) in diff.merged() {
+        writeln!(writer, "merged\t{} -> {}", old.join(" "), new)?;
+    }
+    for (old, new) in diff.split() {
+        writeln!(writer, "split\t{} -> {}", old, new.join(" "))?;
+    }
+    writeln!(
+        writer,
+        "{} of {} words changed key; {} keys merged and {} split, \
+         gaining {} pairs of matching words and losing {}",
+        diff.changed().len(),
+        diff.words(),
+        diff.merged().len(),
+        diff.split().len(),
+        diff.matches_gained(),
+        diff.matches_lost()
+    )?;
+    writer.flush()
+}
+
+// Reads `reader` a chunk of lines at a time and writes every word with its
+// key. A chunk ends when the reader has nothing more buffered, so text typed
+// at a terminal comes straight back, while a file is read in big chunks that
+// are normalized in parallel. Output is flushed whenever the input pauses.
+fn normalize_stream<R: Read, W: Write>(
+    mut reader: BufReader<R>,
+    mut writer: RecordWriter<W>,
+    options: &Options,
+) -> io::Result<()> {
+    let mut text = String::new();
+    let mut line = Vec::new();
+    loop {
+        text.clear();
+        let mut words = 0;
+        let mut eof = false;
+        while words < CHUNK_WORDS {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                eof = true;
+                break;
+            }
+            let line = String::from_utf8_lossy(&line);
+            words += line.split_whitespace().count();
+            text.push_str(&line);
+            if reader.buffer().is_empty() {
+                break;
+            }
+        }
+
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let keys = normalize_batch_with(&words, options);
+        for (word, key) in words.iter().zip(&keys) {
+            writer.write(word, key, options)?;
+        }
+        if eof {
+            return writer.flush();
+        }
+        if reader.buffer().is_empty() {
+            writer.flush()?;
+        }
+    }
+}
+
+// The words of `reader`, read a line at a time and decoded like normalize
+// decodes them, with anything that isn't UTF-8 replaced. The text is never
+// held in memory all at once. A read error ends the words and is left in
+// `error` for the caller to check once it has them all.
+fn read_words<'a, R: BufRead + 'a>(
+    mut reader: R,
+    error: &'a mut Option<io::Error>,
+) -> impl Iterator<Item = String> + 'a {
+    let mut line = Vec::new();
+    std::iter::from_fn(move || {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&line);
+                Some(
+                    line.split_whitespace()
+                        .map(str::to_string)
+                        .collect::<Vec<_>>(),
+                )
+            }
+            Err(e) => {
+                *error = Some(e);
+                None
+            }
+        }
+    })
+    .flatten()
+}
+
+fn reader(path: &str) -> io::Result<BufReader<Box<dyn Read>>> {
+    let input: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path).map_err(|e| with_path(e, path))?)
+    };
+    Ok(BufReader::with_capacity(1 << 16, input))
+}
+
+fn writer(path: &str) -> io::Result<Box<dyn Write>> {
+    if path == "-" {
+        Ok(Box::new(BufWriter::new(io::stdout())))
+    } else {
+        let file = File::create(path).map_err(|e| with_path(e, path))?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+}
+
+fn bad_line(path: &str, number: usize, problem: &str) -> io::Error {
+    let path = if path == "-" { "stdin" } else { path };
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}:{}: {}", path, number, problem),
+    )
+}
+
+fn with_path(e: io::Error, path: &str) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path, e))
 }
@@ -0,0 +1,356 @@
+use std::borrow::Cow;
+
+use crate::arabic::{fold_arabic_name, transliterate_arabic};
+use crate::ascii_fold::{latinize, latinize_into, latinize_with, lowercase_with, Locale};
+use crate::chinese::{fold_chinese_name, transliterate_han_surnames};
+use crate::exceptions::Exceptions;
+use crate::greek::transliterate_greek;
+use crate::hebrew::transliterate_hebrew;
+use crate::indic::{fold_indic_name, transliterate_devanagari};
+use crate::japanese::{fold_japanese_name, transliterate_kana};
+use crate::korean::{fold_korean_name, transliterate_hangul};
+use crate::rules::Rules;
+
+/// A non-Latin script that can be transliterated to Latin letters before the
+/// English rules run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// Greek, using ELOT 743.
+    Greek,
+    /// Arabic, along with the Persian and Urdu letters.
+    Arabic,
+    /// Hebrew, pointed or unpointed, and Yiddish.
+    Hebrew,
+    /// Chinese characters, but only the common surnames. Other characters
+    /// are left alone.
+    Han,
+    /// Japanese hiragana and katakana, using Hepburn romaji.
+    Kana,
+    /// Korean Hangul, using the Revised Romanization.
+    Hangul,
+    /// Devanagari, using IAST.
+    Devanagari,
+}
+
+/// The language a name comes from, for name-specific folding of its Latin
+/// spellings. This runs after transliteration and before the English rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NameOrigin {
+    /// Arabic names are folded to a consonant skeleton so that "Mohammed",
+    /// "Muhammad" and "Mehmet" match. The definite article is dropped.
+    Arabic,
+    /// Chinese names are folded across Pinyin, Wade-Giles and Hong Kong
+    /// Cantonese spellings, so "Zhang", "Chang" and "Cheung" match.
+    Chinese,
+    /// Japanese names are folded to Hepburn with long vowels shortened, so
+    /// "Ohno", "Oono" and "Ōno" match, as do Kunrei spellings like "Tutomu".
+    Japanese,
+    /// Korean names are folded across the Revised Romanization,
+    /// McCune-Reischauer and popular spellings, so "Park", "Pak" and "Bak"
+    /// match.
+    Korean,
+    /// Indian names have their sibilants, aspirated consonants and long
+    /// vowel spellings folded, so "Lakshmi" and "Laxmi" match, as do
+    /// "Deepak" and "Dipak".
+    Indic,
+}
+
+/// Settings for the optional stages of normalization. The default enables
+/// none of them, which gives the same keys as `normalize_word`.
+///
+/// ```
+/// use phonetic_normalizer::{normalize_word_with, Options, Script};
+///
+/// let options = Options::new().script(Script::Greek);
+/// assert_eq!(normalize_word_with("Παπαδόπουλος", &options), "papatopolos");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    scripts: Vec<Script>,
+    names: Option<NameOrigin>,
+    locale: Option<Locale>,
+    rules: Rules,
+    exceptions: Exceptions,
+    // inverted, so that the derived `Default` leaves silent letter dropping on
+    keep_silent_letters: bool,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// Transliterate `script` to Latin letters before normalizing.
+    pub fn script(mut self, script: Script) -> Options {
+        if !self.scripts.contains(&script) {
+            self.scripts.push(script);
+        }
+        self
+    }
+
+    pub fn scripts(&self) -> &[Script] {
+        &self.scripts
+    }
+
+    /// Treat every word as a name of the given origin.
+    pub fn names(mut self, origin: NameOrigin) -> Options {
+        self.names = Some(origin);
+        self
+    }
+
+    pub fn name_origin(&self) -> Option<NameOrigin> {
+        self.names
+    }
+
+    /// Lowercase and latinize following the conventions of `locale`.
+    pub fn locale(mut self, locale: Locale) -> Options {
+        self.locale = Some(locale);
+        self
+    }
+
+    pub fn fold_locale(&self) -> Option<Locale> {
+        self.locale
+    }
+
+    /// Apply `rules` to each word before the English rules. The patterns are
+    /// matched against the word after it has been latinized, folded as a
+    /// name if `names` is set and lowercased.
+    pub fn rules(mut self, rules: Rules) -> Options {
+        self.rules = rules;
+        self
+    }
+
+    pub fn user_rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Look words up in `exceptions` before the English rules, instead of
+    /// only in the built-in list. The words are matched as the English rules
+    /// would first see them, after any other stages.
+    pub fn exceptions(mut self, exceptions: Exceptions) -> Options {
+        self.exceptions = exceptions;
+        self
+    }
+
+    pub fn word_exceptions(&self) -> &Exceptions {
+        &self.exceptions
+    }
+
+    /// Whether to drop silent letters: the w in wr, the h in rh, the p in ps
+    /// and pn and the m in mn at the start of a word, the b in mb and the l
+    /// in alk and olk at the end, and the b in bt after e or ou. This is on
+    /// by default. Turning it off only disables this group, so "lamb"
+    /// matches "lamp" again, while the exceptions and every other rule still
+    /// run.
+    pub fn silent_letters(mut self, enabled: bool) -> Options {
+        self.keep_silent_letters = !enabled;
+        self
+    }
+
+    pub fn drops_silent_letters(&self) -> bool {
+        !self.keep_silent_letters
+    }
+
+    // Runs each of the enabled transliteration stages over `text`.
+    pub(crate) fn transliterate<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut output = Cow::Borrowed(text);
+        for script in &self.scripts {
+            output = match script {
+                Script::Greek => Cow::Owned(transliterate_greek(&output)),
+                Script::Arabic => Cow::Owned(transliterate_arabic(&output)),
+                Script::Hebrew => Cow::Owned(transliterate_hebrew(&output)),
+                Script::Han => Cow::Owned(transliterate_han_surnames(&output)),
+                Script::Kana => Cow::Owned(transliterate_kana(&output)),
+                Script::Hangul => Cow::Owned(transliterate_hangul(&output)),
+                Script::Devanagari => Cow::Owned(transliterate_devanagari(&output)),
+            };
+        }
+        output
+    }
+
+    // Like `prepare`, but appends to `dest`. Without any stages enabled this
+    // only latinizes, which doesn't need to allocate.
+    pub(crate) fn prepare_into(&self, source: &str, dest: &mut String) {
+        if self.scripts.is_empty()
+            && self.locale.is_none()
+            && self.names.is_none()
+            && self.rules.is_empty()
+        {
+            latinize_into(source, dest);
+        } else {
+            dest.push_str(&self.prepare(source));
+        }
+    }
+
+    // Runs everything that comes before the English rules: transliteration,
+    // latinizing, name folding and the user's rules.
+    pub(crate) fn prepare<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        self.prepare_traced(source, |_, _| {})
+    }
+
+    // Like `prepare`, calling `record` with the name of each stage that runs
+    // and its output.
+    pub(crate) fn prepare_traced<'a>(
+        &self,
+        source: &'a str,
+        mut record: impl FnMut(&'static str, &str),
+    ) -> Cow<'a, str> {
+        let text = self.transliterate(source);
+        if !self.scripts.is_empty() {
+            record("transliterate", &text);
+        }
+        let latin = match (self.locale, text) {
+            (Some(locale), text) => {
+                let lower = lowercase_with(locale, &text);
+                Cow::Owned(latinize_with(locale, &lower).into_owned())
+            }
+            (None, Cow::Borrowed(text)) => latinize(text),
+            (None, Cow::Owned(text)) => {
+                let folded = match latinize(&text) {
+                    Cow::Owned(folded) => Some(folded),
+                    Cow::Borrowed(_) => None,
+                };
+                Cow::Owned(folded.unwrap_or(text))
+            }
+        };
+        record("latinize", &latin);
+        let folded = match self.names {
+            Some(origin) => {
+                let folded = fold_name(origin, latin.into_owned());
+                record("names", &folded);
+                Cow::Owned(folded)
+            }
+            None => latin,
+        };
+        if self.rules.is_empty() {
+            return folded;
+        }
+
+        let mut folded = folded.into_owned();
+        folded.make_ascii_lowercase();
+        let replaced = match self.rules.apply(&folded) {
+            Cow::Owned(replaced) => replaced,
+            Cow::Borrowed(_) => folded,
+        };
+        record("rules", &replaced);
+        Cow::Owned(replaced)
+    }
+}
+
+fn fold_name(origin: NameOrigin, mut latin: String) -> String {
+    latin.make_ascii_lowercase();
+    match origin {
+        NameOrigin::Arabic => fold_arabic_name(&latin),
+        NameOrigin::Chinese => fold_chinese_name(&latin),
+        NameOrigin::Japanese => fold_japanese_name(&latin),
+        NameOrigin::Korean => fold_korean_name(&latin),
+        NameOrigin::Indic => fold_indic_name(&latin),
+    }
+}
+
+/// Normalizes words using a fixed set of `Options`. Useful when the same
+/// settings apply to every word in a data set.
+///
+/// `normalize_into` reuses the normalizer's scratch space and the caller's
+/// output buffer, so once they have grown to fit the longest word a loop over
+/// it doesn't allocate. The exceptions are the transliteration, locale and
+/// name stages and any user rules, which still build their own strings.
+///
+/// ```
+/// use phonetic_normalizer::{normalize_word, Normalizer};
+///
+/// let mut normalizer = Normalizer::default();
+/// let mut key = String::new();
+/// for word in &["Philip", "Phillip", "Filip"] {
+///     normalizer.normalize_into(word, &mut key);
+///     assert_eq!(key, normalize_word(word));
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Normalizer {
+    options: Options,
+    scratch: String,
+}
+
+impl Normalizer {
+    pub fn new(options: Options) -> Normalizer {
+        Normalizer {
+            options,
+            scratch: String::new(),
+        }
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    pub fn normalize<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        crate::normalize_word_with(word, &self.options)
+    }
+
+    /// Normalize `word` into `dest`, replacing what was there. The result is
+    /// the same as `normalize`.
+    pub fn normalize_into(&mut self, word: &str, dest: &mut String) {
+        dest.clear();
+        self.options.prepare_into(word, dest);
+        crate::apply_rules(dest, &mut self.scratch, &self.options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &[&str] = &[
+        "Philip",
+        "Phillip",
+        "Filip",
+        "Catherine",
+        "Kathryn",
+        "Knight",
+        "Night",
+        "Thompson",
+        "Tomson",
+        "Plough",
+        "Colour",
+        "Gnome",
+        "Xavier",
+        "Sean",
+        "Shawn",
+        "Bartholomew",
+        "Noël",
+        "Jose\u{0301}",
+        "Müller",
+        "Straße",
+        "QUEEN",
+        "a",
+        "",
+        "-",
+        "O'Brien",
+        "Smith",
+        "Иван",
+        "Colonel",
+        "Siobhán",
+    ];
+
+    #[test]
+    fn normalize_into_matches_normalize_word() {
+        let mut normalizer = Normalizer::default();
+        let mut reused = String::new();
+        let mut fresh = String::new();
+        for word in WORDS {
+            let expected = crate::normalize_word(word);
+            normalizer.normalize_into(word, &mut reused);
+            crate::normalize_into(word, &mut fresh);
+            assert_eq!(reused, expected, "{}", word);
+            assert_eq!(fresh, expected, "{}", word);
+        }
+
+        let options = Options::new().script(Script::Greek).locale(Locale::German);
+        let mut normalizer = Normalizer::new(options.clone());
+        for word in WORDS.iter().chain(&["Παπαδόπουλος"]) {
+            normalizer.normalize_into(word, &mut reused);
+            assert_eq!(reused, crate::normalize_word_with(word, &options));
+        }
+    }
+}
@@ -0,0 +1,401 @@
+/*
+* The record formats of the command line tool.
+*
+* Each normalized word becomes one record: the word and its key, followed by
+* whichever optional fields were asked for. Plain output is the original
+* `word\tkey` with no header or quoting. The structured formats escape their
+* values so a word can hold any character, and CSV and TSV start with a
+* header naming the fields. The groups of the cluster command are written
+* the same way, one record per group.
+*/
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use phonetic_normalizer::{daitch_mokotoff, explain, Group, Options, Trace, ALGORITHM_VERSION};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Plain,
+    Jsonl,
+    Csv,
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Format, String> {
+        match name {
+            "plain" => Ok(Format::Plain),
+            "jsonl" => Ok(Format::Jsonl),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            _ => Err(format!(
+                "unknown format `{}`, expected plain, jsonl, csv or tsv",
+                name
+            )),
+        }
+    }
+}
+
+/// The optional fields that follow the word and key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fields {
+    pub version: bool,
+    pub latinized: bool,
+    pub dm: bool,
+    pub trace: bool,
+}
+
+impl FromStr for Fields {
+    type Err = String;
+
+    // A comma separated list of field names.
+    fn from_str(names: &str) -> Result<Fields, String> {
+        let mut fields = Fields::default();
+        for name in names.split(',').map(str::trim) {
+            match name {
+                "version" => fields.version = true,
+                "latinized" => fields.latinized = true,
+                "dm" => fields.dm = true,
+                "trace" => fields.trace = true,
+                _ => {
+                    return Err(format!(
+                        "unknown field `{}`, expected version, latinized, dm or trace",
+                        name
+                    ))
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+enum Value<'a> {
+    Text(&'a str),
+    Number(u64),
+    List(Vec<String>),
+    Trace(&'a Trace),
+}
+
+/// Writes records in one of the formats.
+pub struct RecordWriter<W: Write> {
+    writer: W,
+    format: Format,
+    fields: Fields,
+    header: bool,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(writer: W, format: Format, fields: Fields) -> RecordWriter<W> {
+        RecordWriter {
+            writer,
+            format,
+            fields,
+            header: matches!(format, Format::Csv | Format::Tsv),
+        }
+    }
+
+    /// Write the record for `word`, whose key is `key`. The optional fields
+    /// are worked out with `options`.
+    pub fn write(&mut self, word: &str, key: &str, options: &Options) -> io::Result<()> {
+        if self.header {
+            self.header = false;
+            let names = self.names();
+            self.write_row(names.iter().map(|name| Value::Text(name)), &names)?;
+        }
+
+        let fields = self.fields;
+        let trace = if fields.latinized || fields.trace {
+            Some(explain(word, options))
+        } else {
+            None
+        };
+        let mut values = vec![Value::Text(word), Value::Text(key)];
+        if fields.version {
+            values.push(Value::Number(ALGORITHM_VERSION.into()));
+        }
+        if let (true, Some(trace)) = (fields.latinized, &trace) {
+            values.push(Value::Text(trace.latinized()));
+        }
+        if fields.dm {
+            values.push(Value::List(daitch_mokotoff(word)));
+        }
+        if let (true, Some(trace)) = (fields.trace, &trace) {
+            values.push(Value::Trace(trace));
+        }
+        let names = self.names();
+        self.write_row(values.into_iter(), &names)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    // The names of the fields in each record, in order.
+    fn names(&self) -> Vec<&'static str> {
+        let fields = self.fields;
+        [
+            ("word", true),
+            ("key", true),
+            ("version", fields.version),
+            ("latinized", fields.latinized),
+            ("dm", fields.dm),
+            ("trace", fields.trace),
+        ]
+        .iter()
+        .filter(|(_, included)| *included)
+        .map(|(name, _)| *name)
+        .collect()
+    }
+
+    // JSON needs the `names` of the values, which the other formats give in
+    // their header instead.
+    fn write_row<'a>(
+        &mut self,
+        values: impl Iterator<Item = Value<'a>>,
+        names: &[&str],
+    ) -> io::Result<()> {
+        if self.format == Format::Jsonl {
+            let w = &mut self.writer;
+            write!(w, "{{")?;
+            for (i, (name, value)) in names.iter().zip(values).enumerate() {
+                if i > 0 {
+                    write!(w, ",")?;
+                }
+                write!(w, "{}:", Json(name))?;
+                write_json(w, &value)?;
+            }
+            return writeln!(w, "}}");
+        }
+
+        let w = &mut self.writer;
+        let (separator, end) = match self.format {
+            Format::Csv => (",", "\r\n"),
+            _ => ("\t", "\n"),
+        };
+        for (i, value) in values.enumerate() {
+            if i > 0 {
+                write!(w, "{}", separator)?;
+            }
+            let text = flatten(&value);
+            match self.format {
+                Format::Csv => write!(w, "{}", Csv(&text))?,
+                Format::Tsv => write!(w, "{}", Tsv(&text))?,
+                _ => write!(w, "{}", text)?,
+            }
+        }
+        write!(w, "{}", end)
+    }
+}
+
+/// Write each group as a record of its key, the number of words in it
+/// counting repeats, and its distinct words. The delimited formats separate
+/// the words with spaces.
+pub fn write_groups<W: Write>(writer: W, format: Format, groups: &[Group]) -> io::Result<()> {
+    let mut writer = RecordWriter {
+        writer,
+        format,
+        fields: Fields::default(),
+        header: matches!(format, Format::Csv | Format::Tsv),
+    };
+    let names = ["key", "count", "words"];
+    if writer.header {
+        writer.write_row(names.iter().map(|name| Value::Text(name)), &names)?;
+    }
+    for group in groups {
+        let values = vec![
+            Value::Text(group.key()),
+            Value::Number(group.count() as u64),
+            Value::List(group.words().to_vec()),
+        ];
+        writer.write_row(values.into_iter(), &names)?;
+    }
+    writer.flush()
+}
+
+fn write_json<W: Write>(w: &mut W, value: &Value) -> io::Result<()> {
+    match value {
+        Value::Text(text) => write!(w, "{}", Json(text)),
+        Value::Number(number) => write!(w, "{}", number),
+        Value::List(items) => {
+            write!(w, "[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(w, ",")?;
+                }
+                write!(w, "{}", Json(item))?;
+            }
+            write!(w, "]")
+        }
+        Value::Trace(trace) => {
+            write!(w, "[")?;
+            for (i, step) in trace.steps().iter().enumerate() {
+                if i > 0 {
+                    write!(w, ",")?;
+                }
+                write!(
+                    w,
+                    "{{\"stage\":{},\"output\":{}}}",
+                    Json(step.stage),
+                    Json(&step.output)
+                )?;
+            }
+            write!(w, "]")
+        }
+    }
+}
+
+// A value as a single string for the delimited formats: lists are separated
+// by spaces and a trace is `stage=output` pairs separated by semicolons.
+fn flatten(value: &Value) -> String {
+    match value {
+        Value::Text(text) => text.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::List(items) => items.join(" "),
+        Value::Trace(trace) => trace
+            .steps()
+            .iter()
+            .map(|step| format!("{}={}", step.stage, step.output))
+            .collect::<Vec<_>>()
+            .join(";"),
+    }
+}
+
+/// Displays a string as a quoted JSON string.
+pub struct Json<'a>(pub &'a str);
+
+impl fmt::Display for Json<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in self.0.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+/// Displays a string as an RFC 4180 CSV field, quoted only if it has to be.
+pub struct Csv<'a>(pub &'a str);
+
+impl fmt::Display for Csv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.0.contains(['"', ',', '\r', '\n']) {
+            return write!(f, "{}", self.0);
+        }
+        write!(f, "\"{}\"", self.0.replace('"', "\"\""))
+    }
+}
+
+/// Displays a string as a TSV field, with backslash escapes for tabs, line
+/// breaks and backslashes.
+pub struct Tsv<'a>(pub &'a str);
+
+impl fmt::Display for Tsv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' => write!(f, "\\\\")?,
+                '\t' => write!(f, "\\t")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(format: Format, fields: &str, words: &[&str]) -> String {
+        let fields = if fields.is_empty() {
+            Fields::default()
+        } else {
+            fields.parse().unwrap()
+        };
+        let mut writer = RecordWriter::new(Vec::new(), format, fields);
+        for word in words {
+            let key = phonetic_normalizer::normalize_word(word);
+            writer.write(word, &key, &Options::default()).unwrap();
+        }
+        String::from_utf8(writer.writer).unwrap()
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(Json("a\"b\\c\td\u{1}").to_string(), r#""a\"b\\c\td\u0001""#);
+        assert_eq!(Csv("plain").to_string(), "plain");
+        assert_eq!(Csv("a,b").to_string(), "\"a,b\"");
+        assert_eq!(Csv("say \"hi\"").to_string(), "\"say \"\"hi\"\"\"");
+        assert_eq!(Csv("two\nlines").to_string(), "\"two\nlines\"");
+        assert_eq!(Tsv("a\tb\\c\nd").to_string(), "a\\tb\\\\c\\nd");
+    }
+
+    #[test]
+    fn plain_is_unchanged() {
+        assert_eq!(
+            records(Format::Plain, "", &["color", "colour"]),
+            "color\tkolor\ncolour\tkolor\n"
+        );
+    }
+
+    #[test]
+    fn delimited_formats_have_a_header() {
+        assert_eq!(
+            records(Format::Csv, "version", &["Phillip"]),
+            format!(
+                "word,key,version\r\nPhillip,filip,{}\r\n",
+                ALGORITHM_VERSION
+            )
+        );
+        assert_eq!(
+            records(Format::Tsv, "latinized,dm", &["Zoë"]),
+            "word\tkey\tlatinized\tdm\nZoë\tzoe\tZoe\t400000\n"
+        );
+    }
+
+    #[test]
+    fn jsonl_fields() {
+        let line = records(Format::Jsonl, "latinized,trace", &["Zoë"]);
+        assert!(line.starts_with(r#"{"word":"Zoë","key":"zoe","latinized":"Zoe","trace":[{"stage":"latinize","output":"Zoe"},"#));
+        assert!(line.ends_with("]}\n"));
+        assert_eq!(line.lines().count(), 1);
+    }
+
+    #[test]
+    fn groups() {
+        let groups =
+            phonetic_normalizer::group_by_key(["Smith", "Smyth", "Smith"], &Options::default());
+        let mut output = Vec::new();
+        write_groups(&mut output, Format::Tsv, &groups).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "key\tcount\twords\nsmith\t3\tSmith Smyth\n"
+        );
+        let mut output = Vec::new();
+        write_groups(&mut output, Format::Jsonl, &groups).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"key\":\"smith\",\"count\":3,\"words\":[\"Smith\",\"Smyth\"]}\n"
+        );
+    }
+
+    #[test]
+    fn unknown_names() {
+        assert!("yaml".parse::<Format>().is_err());
+        assert!("version,color".parse::<Fields>().is_err());
+    }
+}
@@ -0,0 +1,270 @@
+/*
+* Substitution rules matched with an Aho-Corasick automaton.
+*
+* A set of rules is a list of phases. Each phase is a set of pattern and
+* replacement pairs that is compiled into a single automaton, so a phase makes
+* one pass over the word however many rules it has. Where several patterns
+* match at the same place the longest one wins, and the pass then carries on
+* after the match, so matches never overlap and a replacement is never
+* matched again by the same phase. The next phase runs over the output of the
+* one before, which is how rules that feed each other are ordered.
+*
+* User rules, passed in with `Options::rules`, run through this, and so do
+* the English rules that only depend on the letters they replace: the early
+* ones like ought, plough, ight and our in `fold_doubles`, which are used by
+* both versions of the English rules. The rest depend on where in the word
+* they match or on the letters around them, and the byte pass in
+* byte_rules.rs checks them with one match per byte.
+*/
+
+use std::borrow::Cow;
+use std::fmt;
+
+use aho_corasick::{AhoCorasick, MatchKind};
+
+/// Ordered phases of substitutions to apply to a word.
+///
+/// Within a phase, the leftmost match wins, and of the patterns that match
+/// there the longest wins; if two patterns are the same, the first one added
+/// is used. Matching carries on after the replaced text, so matches don't
+/// overlap. Each phase runs over the output of the phase before.
+///
+/// ```
+/// use phonetic_normalizer::Rules;
+///
+/// let rules = Rules::new()
+///     .phase(vec![("ph", "f"), ("pph", "f"), ("ck", "k")])
+///     .phase(vec![("ff", "f")]);
+/// assert_eq!(rules.apply("sapphire"), "safire");
+/// assert_eq!(rules.apply("phfft"), "fft");
+/// assert_eq!(rules.apply("duck"), "duk");
+/// assert_eq!(rules.apply("dog"), "dog");
+/// ```
+#[derive(Clone, Default)]
+pub struct Rules {
+    phases: Vec<Phase>,
+}
+
+#[derive(Clone)]
+struct Phase {
+    rules: Vec<(String, String)>,
+    automaton: AhoCorasick,
+}
+
+impl Rules {
+    pub fn new() -> Rules {
+        Rules::default()
+    }
+
+    /// Add a phase that runs after the ones already added. Empty patterns
+    /// are ignored, since they would match everywhere.
+    ///
+    /// # Panics
+    ///
+    /// If the patterns are too large to compile into an automaton, which
+    /// takes many thousands of them.
+    pub fn phase<I, P, R>(mut self, rules: I) -> Rules
+    where
+        I: IntoIterator<Item = (P, R)>,
+        P: AsRef<str>,
+        R: AsRef<str>,
+    {
+        let rules: Vec<(String, String)> = rules
+            .into_iter()
+            .filter(|(pattern, _)| !pattern.as_ref().is_empty())
+            .map(|(pattern, replacement)| {
+                (
+                    pattern.as_ref().to_string(),
+                    replacement.as_ref().to_string(),
+                )
+            })
+            .collect();
+        if rules.is_empty() {
+            return self;
+        }
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            // The text is nearly always a single word, too short for a
+            // prefilter to win back what it costs to start one.
+            .prefilter(false)
+            .build(rules.iter().map(|(pattern, _)| pattern))
+            .expect("the rules fit in an automaton");
+        self.phases.push(Phase { rules, automaton });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    /// The rules in each phase, as pattern and replacement pairs.
+    pub fn phases(&self) -> impl Iterator<Item = &[(String, String)]> {
+        self.phases.iter().map(|phase| phase.rules.as_slice())
+    }
+
+    /// Apply every phase to `text`. If no rule matches, `text` is returned
+    /// unchanged.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut output = Cow::Borrowed(text);
+        let mut scratch = String::new();
+        for phase in &self.phases {
+            if phase.replace(&output, &mut scratch) {
+                output = Cow::Owned(std::mem::take(&mut scratch));
+            }
+        }
+        output
+    }
+}
+
+impl Rules {
+    // Applies every phase to `text[start..]` in place, leaving the start of
+    // `text` alone. Each phase that matches writes into `scratch` and is
+    // copied back, so once both are big enough nothing is allocated.
+    pub(crate) fn apply_from(&self, text: &mut String, start: usize, scratch: &mut String) {
+        for phase in &self.phases {
+            scratch.clear();
+            scratch.push_str(&text[..start]);
+            if phase.replace(&text[start..], scratch) {
+                text.clear();
+                text.push_str(scratch);
+            }
+        }
+    }
+
+    // The same for the bytes of an ASCII word in `buf[..len]`, returning its
+    // new length. `scratch` must be as long as `buf`, and the replacements
+    // must fit in it.
+    pub(crate) fn apply_bytes_from(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        start: usize,
+        scratch: &mut [u8],
+    ) -> usize {
+        let mut len = len;
+        for phase in &self.phases {
+            let mut matches = phase.automaton.find_iter(&buf[start..len]).peekable();
+            if matches.peek().is_none() {
+                continue;
+            }
+            let mut k = 0;
+            let mut last = start;
+            let mut copy = |bytes: &[u8], k: &mut usize| {
+                scratch[*k..*k + bytes.len()].copy_from_slice(bytes);
+                *k += bytes.len();
+            };
+            copy(&buf[..start], &mut k);
+            for found in matches {
+                copy(&buf[last..start + found.start()], &mut k);
+                copy(phase.rules[found.pattern().as_usize()].1.as_bytes(), &mut k);
+                last = start + found.end();
+            }
+            copy(&buf[last..len], &mut k);
+            buf[..k].copy_from_slice(&scratch[..k]);
+            len = k;
+        }
+        len
+    }
+}
+
+impl Phase {
+    // Appends `text` with this phase's replacements to `output` and returns
+    // true, or returns false without touching `output` if nothing matched.
+    fn replace(&self, text: &str, output: &mut String) -> bool {
+        let mut matches = self.automaton.find_iter(text).peekable();
+        if matches.peek().is_none() {
+            return false;
+        }
+        let mut last = 0;
+        for found in matches {
+            output.push_str(&text[last..found.start()]);
+            output.push_str(&self.rules[found.pattern().as_usize()].1);
+            last = found.end();
+        }
+        output.push_str(&text[last..]);
+        true
+    }
+}
+
+impl PartialEq for Rules {
+    fn eq(&self, other: &Rules) -> bool {
+        self.phases().eq(other.phases())
+    }
+}
+
+impl Eq for Rules {}
+
+impl fmt::Debug for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.phases()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leftmost_longest() {
+        let rules = Rules::new().phase(vec![("a", "1"), ("ab", "2"), ("abc", "3"), ("bcd", "4")]);
+        // abc starts first, so bcd never gets a chance
+        assert_eq!(rules.apply("abcd"), "3d");
+        assert_eq!(rules.apply("abab"), "22");
+        assert_eq!(rules.apply("bcda"), "41");
+        assert_eq!(rules.apply("xyz"), "xyz");
+    }
+
+    #[test]
+    fn first_duplicate_wins() {
+        let rules = Rules::new().phase(vec![("ou", "o"), ("ou", "u")]);
+        assert_eq!(rules.apply("colour"), "color");
+    }
+
+    #[test]
+    fn replacements_are_not_rematched() {
+        let rules = Rules::new().phase(vec![("x", "ks"), ("ks", "x")]);
+        assert_eq!(rules.apply("xks"), "ksx");
+    }
+
+    #[test]
+    fn phases_run_in_order() {
+        let rules = Rules::new()
+            .phase(vec![("oo", "u")])
+            .phase(vec![("wu", "u")]);
+        assert_eq!(rules.apply("woo"), "u");
+
+        let rules = Rules::new()
+            .phase(vec![("wu", "u")])
+            .phase(vec![("oo", "u")]);
+        assert_eq!(rules.apply("woo"), "wu");
+    }
+
+    #[test]
+    fn unchanged_text_is_borrowed() {
+        let rules = Rules::new().phase(vec![("q", "k")]);
+        assert!(matches!(rules.apply("smith"), Cow::Borrowed(_)));
+        assert!(matches!(Rules::new().apply("smith"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn empty_patterns_are_ignored() {
+        let rules = Rules::new().phase(vec![("", "x")]);
+        assert!(rules.is_empty());
+        assert_eq!(rules.apply("abc"), "abc");
+    }
+
+    #[test]
+    fn multibyte_text() {
+        let rules = Rules::new().phase(vec![("ß", "ss"), ("ö", "oe")]);
+        assert_eq!(rules.apply("größe"), "groesse");
+    }
+
+    #[test]
+    fn many_rules() {
+        let patterns: Vec<(String, String)> = (0..5000)
+            .map(|i| (format!("w{}x", i), format!("<{}>", i)))
+            .collect();
+        let rules = Rules::new().phase(patterns);
+        assert_eq!(rules.apply("aw17xw4999xb"), "a<17><4999>b");
+    }
+}
@@ -0,0 +1,92 @@
+// Checks that a warmed up Normalizer doesn't allocate. This is its own test
+// binary because it replaces the global allocator with one that counts.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use phonetic_normalizer::{normalize_word, Normalizer};
+
+// Counts the allocations made on each thread, so the tests running in
+// parallel don't see each other's.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const WORDS: &[&str] = &[
+    "Philip",
+    "Phillip",
+    "Filip",
+    "Catherine",
+    "Kathryn",
+    "Knight",
+    "Night",
+    "Thompson",
+    "Tomson",
+    "Plough",
+    "Colour",
+    "Gnome",
+    "Xavier",
+    "Sean",
+    "Shawn",
+    "Bartholomew",
+    "Noël",
+    "Jose\u{0301}",
+    "Müller",
+    "Straße",
+    "QUEEN",
+    "a",
+    "",
+    "-",
+    "O'Brien",
+    "Smith",
+    "Иван",
+    "Colonel",
+    "Siobhán",
+    "Wright",
+    "Lambs",
+];
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn no_allocations_after_warm_up() {
+    let mut normalizer = Normalizer::default();
+    let mut key = String::new();
+    for word in WORDS {
+        normalizer.normalize_into(word, &mut key);
+    }
+
+    let before = allocations();
+    for _ in 0..10 {
+        for word in WORDS {
+            normalizer.normalize_into(word, &mut key);
+        }
+    }
+    assert_eq!(allocations(), before);
+    // make sure the allocations are actually being counted
+    let _ = normalize_word("Philip");
+    assert!(allocations() > before);
+}